@@ -0,0 +1,601 @@
+//! Decoding of the events the program logs for every change to a record
+
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    bytemuck::Pod,
+    gossip_weight_controller_interface::{
+        event::{
+            AuthorityChangeEvent, CloseEvent, EventHeader, EventType, InitializeEvent,
+            ReallocateEvent, SignersChangeEvent, WriteEvent,
+        },
+        id,
+    },
+    solana_pubkey::Pubkey,
+    std::fmt,
+};
+
+/// Prefix the runtime puts before data logged with `sol_log_data`
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Prefix of the lines the runtime logs about the programs it runs
+const PROGRAM_PREFIX: &str = "Program ";
+
+/// Change to a record reconstructed from the transaction logs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    /// Record that changed
+    pub record: Pubkey,
+    /// Account that approved the change
+    pub signer: Pubkey,
+    /// Slot in which the change was made
+    pub slot: u64,
+    /// What changed
+    pub kind: EventKind,
+}
+
+/// Old and new values of a change to a record
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventKind {
+    /// Record initialized with an authority
+    Initialize { authority: Pubkey },
+    /// Bytes at an offset of the writable data replaced
+    Write {
+        offset: u64,
+        old_data: Vec<u8>,
+        new_data: Vec<u8>,
+    },
+    /// Authority handed over
+    AuthorityChange {
+        old_authority: Pubkey,
+        new_authority: Pubkey,
+    },
+    /// Signers approving for the authority replaced
+    SignersChange {
+        authority: Pubkey,
+        old_threshold: u8,
+        old_signers: Vec<Pubkey>,
+        new_threshold: u8,
+        new_signers: Vec<Pubkey>,
+    },
+    /// Record closed, its lamports sent to a destination
+    Close { destination: Pubkey, lamports: u64 },
+    /// Account data resized
+    Reallocate { old_len: u64, new_len: u64 },
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "slot {} record {} signer {}: ",
+            self.slot, self.record, self.signer
+        )?;
+        match &self.kind {
+            EventKind::Initialize { authority } => write!(f, "initialize, authority {authority}"),
+            EventKind::Write {
+                offset,
+                old_data,
+                new_data,
+            } => write!(
+                f,
+                "write {} bytes at offset {offset}, old {old_data:?}, new {new_data:?}",
+                new_data.len()
+            ),
+            EventKind::AuthorityChange {
+                old_authority,
+                new_authority,
+            } => write!(f, "authority {old_authority} -> {new_authority}"),
+            EventKind::SignersChange {
+                authority,
+                old_threshold,
+                old_signers,
+                new_threshold,
+                new_signers,
+            } => write!(
+                f,
+                "signers of authority {authority}: {old_threshold} of {old_signers:?} -> \
+                 {new_threshold} of {new_signers:?}"
+            ),
+            EventKind::Close {
+                destination,
+                lamports,
+            } => write!(f, "close, {lamports} lamports to {destination}"),
+            EventKind::Reallocate { old_len, new_len } => {
+                write!(f, "reallocate {old_len} -> {new_len} bytes")
+            }
+        }
+    }
+}
+
+/// Read a fixed layout from a logged field
+fn read_field<T: Pod>(field: Option<&Vec<u8>>) -> Option<T> {
+    bytemuck::try_pod_read_unaligned(field?).ok()
+}
+
+/// Read the signers logged in a field
+fn read_signers(field: Option<&Vec<u8>>) -> Option<Vec<Pubkey>> {
+    bytemuck::try_cast_slice::<u8, Pubkey>(field?)
+        .ok()
+        .map(<[Pubkey]>::to_vec)
+}
+
+/// Decode an event from the fields of one `sol_log_data` call, if they hold
+/// one
+pub fn decode_event(fields: &[Vec<u8>]) -> Option<Event> {
+    let mut fields = fields.iter();
+    let header = read_field::<EventHeader>(fields.next())?;
+    let kind = match header.event_type()? {
+        EventType::Initialize => {
+            let body = read_field::<InitializeEvent>(fields.next())?;
+            EventKind::Initialize {
+                authority: body.authority,
+            }
+        }
+        EventType::Write => {
+            let body = read_field::<WriteEvent>(fields.next())?;
+            EventKind::Write {
+                offset: body.offset.into(),
+                old_data: fields.next()?.clone(),
+                new_data: fields.next()?.clone(),
+            }
+        }
+        EventType::AuthorityChange => {
+            let body = read_field::<AuthorityChangeEvent>(fields.next())?;
+            EventKind::AuthorityChange {
+                old_authority: body.old_authority,
+                new_authority: body.new_authority,
+            }
+        }
+        EventType::Close => {
+            let body = read_field::<CloseEvent>(fields.next())?;
+            EventKind::Close {
+                destination: body.destination,
+                lamports: body.lamports.into(),
+            }
+        }
+        EventType::Reallocate => {
+            let body = read_field::<ReallocateEvent>(fields.next())?;
+            EventKind::Reallocate {
+                old_len: body.old_len.into(),
+                new_len: body.new_len.into(),
+            }
+        }
+        EventType::SignersChange => {
+            let body = read_field::<SignersChangeEvent>(fields.next())?;
+            EventKind::SignersChange {
+                authority: body.authority,
+                old_threshold: body.old_threshold,
+                old_signers: read_signers(fields.next())?,
+                new_threshold: body.new_threshold,
+                new_signers: read_signers(fields.next())?,
+            }
+        }
+    };
+    Some(Event {
+        record: header.record,
+        signer: header.signer,
+        slot: header.slot.into(),
+        kind,
+    })
+}
+
+/// Decode an event from the base64 fields of a `Program data:` line
+fn decode_data_line(data: &str) -> Option<Event> {
+    let fields = data
+        .split(' ')
+        .map(|field| BASE64_STANDARD.decode(field).ok())
+        .collect::<Option<Vec<_>>>()?;
+    decode_event(&fields)
+}
+
+/// Reconstruct the events the record program logged in a transaction
+///
+/// Data is only decoded while the record program is the innermost program
+/// running, following the `Program <id> invoke [n]` and `success` or
+/// `failed` lines of the runtime, so that data logged by other programs
+/// cannot pass for an event.
+pub fn decode_events<S: AsRef<str>>(logs: &[S]) -> Vec<Event> {
+    let mut frames = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        let line = line.as_ref();
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if frames.last() == Some(&id()) {
+                events.extend(decode_data_line(data));
+            }
+            continue;
+        }
+        // programs can only log lines starting with `Program log:`,
+        // `Program data:` or `Program return:`, none of which name a program
+        let Some((program_id, status)) = line
+            .strip_prefix(PROGRAM_PREFIX)
+            .and_then(|line| line.split_once(' '))
+        else {
+            continue;
+        };
+        let Ok(program_id) = program_id.parse::<Pubkey>() else {
+            continue;
+        };
+        if status.starts_with("invoke [") {
+            frames.push(program_id);
+        } else if status == "success" || status.starts_with("failed") {
+            frames.pop();
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        gossip_weight_controller::{
+            instruction,
+            processor::process_instruction,
+            state::{RecordData, WeightingConfig, WeightingConfigState, WeightingMode},
+        },
+        solana_account_info::AccountInfo,
+        solana_instruction::Instruction,
+        solana_program::entrypoint::ProgramResult,
+        solana_program_test::{processor, ProgramTest, ProgramTestContext},
+        solana_sdk::{
+            clock::Clock,
+            program_stubs::{set_syscall_stubs, SyscallStubs},
+            rent::Rent,
+            signature::{Keypair, Signer},
+            transaction::Transaction,
+        },
+        solana_system_interface::instruction as system_instruction,
+        std::sync::Once,
+    };
+
+    fn encode(fields: &[&[u8]]) -> String {
+        let fields = fields
+            .iter()
+            .map(|field| BASE64_STANDARD.encode(field))
+            .collect::<Vec<_>>();
+        format!("{PROGRAM_DATA_PREFIX}{}", fields.join(" "))
+    }
+
+    fn header(event_type: EventType) -> EventHeader {
+        EventHeader {
+            event_type: event_type as u8,
+            record: Pubkey::new_from_array([1; 32]),
+            signer: Pubkey::new_from_array([2; 32]),
+            slot: 42.into(),
+        }
+    }
+
+    #[test]
+    fn decode_events_from_logs() {
+        let other_program = Pubkey::new_from_array([9; 32]);
+        let write_header = header(EventType::Write);
+        let write = WriteEvent { offset: 8.into() };
+        let write_data = encode(&[
+            bytemuck::bytes_of(&write_header),
+            bytemuck::bytes_of(&write),
+            &[0, 0],
+            &[5, 6],
+        ]);
+        let authority_header = header(EventType::AuthorityChange);
+        let authority_change = AuthorityChangeEvent {
+            old_authority: Pubkey::new_from_array([3; 32]),
+            new_authority: Pubkey::new_from_array([4; 32]),
+        };
+        let logs = vec![
+            // data logged by another program, even when it decodes as an
+            // event
+            format!("Program {other_program} invoke [1]"),
+            write_data.clone(),
+            format!("Program {other_program} success"),
+            format!("Program {} invoke [1]", id()),
+            "Program log: RecordInstruction::Write".to_string(),
+            write_data.clone(),
+            format!("Program {other_program} invoke [2]"),
+            encode(&[b"unrelated"]),
+            write_data.clone(),
+            format!("Program {other_program} failed: custom program error: 0x0"),
+            // a program can log text that looks like the runtime's
+            format!("Program log: {other_program} invoke [3]"),
+            encode(&[
+                bytemuck::bytes_of(&authority_header),
+                bytemuck::bytes_of(&authority_change),
+            ]),
+            format!("Program {} consumed 1000 of 200000 compute units", id()),
+            format!("Program {} success", id()),
+            write_data,
+        ];
+
+        assert_eq!(
+            decode_events(&logs),
+            vec![
+                Event {
+                    record: write_header.record,
+                    signer: write_header.signer,
+                    slot: 42,
+                    kind: EventKind::Write {
+                        offset: 8,
+                        old_data: vec![0, 0],
+                        new_data: vec![5, 6],
+                    },
+                },
+                Event {
+                    record: authority_header.record,
+                    signer: authority_header.signer,
+                    slot: 42,
+                    kind: EventKind::AuthorityChange {
+                        old_authority: authority_change.old_authority,
+                        new_authority: authority_change.new_authority,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_signers_change() {
+        let authority = Pubkey::new_from_array([3; 32]);
+        let signers = [
+            Pubkey::new_from_array([4; 32]),
+            Pubkey::new_from_array([5; 32]),
+        ];
+        let body = SignersChangeEvent {
+            authority,
+            old_threshold: 0,
+            new_threshold: 2,
+        };
+        assert_eq!(
+            decode_event(&[
+                bytemuck::bytes_of(&header(EventType::SignersChange)).to_vec(),
+                bytemuck::bytes_of(&body).to_vec(),
+                vec![],
+                bytemuck::cast_slice(&signers).to_vec(),
+            ])
+            .unwrap()
+            .kind,
+            EventKind::SignersChange {
+                authority,
+                old_threshold: 0,
+                old_signers: vec![],
+                new_threshold: 2,
+                new_signers: signers.to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn decode_event_rejects_truncated_body() {
+        let close_header = header(EventType::Close);
+        assert_eq!(
+            decode_event(&[bytemuck::bytes_of(&close_header).to_vec(), vec![0; 8]]),
+            None
+        );
+    }
+
+    /// Syscall stubs of `solana-program-test` that also collect logged data
+    ///
+    /// The native runtime of `solana-program-test` only prints data logged
+    /// with `sol_log_data`, so it goes to the logs as a message here and
+    /// [`runtime_logs`] restores the line the runtime would log.
+    struct LogDataStubs(Box<dyn SyscallStubs>);
+
+    /// Prefix of the messages [`LogDataStubs`] log data as
+    const LOG_DATA_PREFIX: &str = "Program log: data: ";
+
+    /// Pass syscalls through to the wrapped stubs
+    macro_rules! delegate {
+        ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) $(-> $ret:ty)?;)*) => {
+            $(fn $name(&self $(, $arg: $ty)*) $(-> $ret)? {
+                self.0.$name($($arg),*)
+            })*
+        };
+    }
+
+    impl SyscallStubs for LogDataStubs {
+        // the syscalls `solana-program-test` implements, any other keeps the
+        // default it falls back to as well
+        delegate! {
+            fn sol_log(&self, message: &str);
+            fn sol_invoke_signed(
+                &self,
+                instruction: &Instruction,
+                account_infos: &[AccountInfo],
+                signers_seeds: &[&[&[u8]]]
+            ) -> ProgramResult;
+            fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64;
+            fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)>;
+            fn sol_set_return_data(&self, data: &[u8]);
+            fn sol_get_stack_height(&self) -> u64;
+        }
+
+        fn sol_log_data(&self, fields: &[&[u8]]) {
+            let fields = fields
+                .iter()
+                .map(|field| BASE64_STANDARD.encode(field))
+                .collect::<Vec<_>>();
+            self.0.sol_log(&format!("data: {}", fields.join(" ")))
+        }
+    }
+
+    /// Put the logs of a native test run in the form the runtime logs them
+    fn runtime_logs(logs: Vec<String>) -> Vec<String> {
+        logs.into_iter()
+            .map(|line| match line.strip_prefix(LOG_DATA_PREFIX) {
+                Some(data) => format!("{PROGRAM_DATA_PREFIX}{data}"),
+                None => line,
+            })
+            .collect()
+    }
+
+    /// Start the program natively, with logged data collected
+    async fn start_program_test() -> ProgramTestContext {
+        let context = ProgramTest::new(
+            "gossip_weight_controller",
+            id(),
+            processor!(process_instruction),
+        )
+        .start_with_context()
+        .await;
+        // the stubs of `solana-program-test` are in place once it started,
+        // and are only taken out for as long as it takes to wrap them
+        static INSTALL_STUBS: Once = Once::new();
+        INSTALL_STUBS.call_once(|| {
+            struct Placeholder;
+            impl SyscallStubs for Placeholder {}
+            let stubs = set_syscall_stubs(Box::new(Placeholder));
+            set_syscall_stubs(Box::new(LogDataStubs(stubs)));
+        });
+        context
+    }
+
+    /// Create a record holding `data_len` bytes after its header
+    async fn create_record(
+        context: &ProgramTestContext,
+        authority: &Keypair,
+        account: &Keypair,
+        data_len: usize,
+    ) {
+        let account_length = std::mem::size_of::<RecordData>() + data_len;
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &account.pubkey(),
+                    Rent::default().minimum_balance(account_length),
+                    account_length as u64,
+                    &id(),
+                ),
+                instruction::initialize(&account.pubkey(), &authority.pubkey()),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, account],
+            context.last_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    /// Simulate a transaction and decode the events in its logs, along with
+    /// the slot they were logged in
+    async fn simulate_events(
+        context: &ProgramTestContext,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> (Vec<Event>, u64) {
+        let mut signers = signers.to_vec();
+        signers.push(&context.payer);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&context.payer.pubkey()),
+            &signers,
+            context.last_blockhash,
+        );
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        let logs = runtime_logs(simulation.simulation_details.unwrap().logs);
+        let slot = context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .slot;
+        (decode_events(&logs), slot)
+    }
+
+    #[tokio::test]
+    async fn decode_events_from_program_test() {
+        let context = start_program_test().await;
+        let authority = Keypair::new();
+        let account = Keypair::new();
+        create_record(&context, &authority, &account, 4).await;
+
+        let (events, slot) = simulate_events(
+            &context,
+            &[instruction::write(&account.pubkey(), &authority.pubkey(), 1, &[5, 6]).unwrap()],
+            &[&authority],
+        )
+        .await;
+        assert_eq!(
+            events,
+            vec![Event {
+                record: account.pubkey(),
+                signer: authority.pubkey(),
+                slot,
+                kind: EventKind::Write {
+                    offset: 1,
+                    old_data: vec![0, 0],
+                    new_data: vec![5, 6],
+                },
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_config_events_from_program_test() {
+        let context = start_program_test().await;
+        let authority = Keypair::new();
+        let account = Keypair::new();
+        let state_len = std::mem::size_of::<WeightingConfigState>();
+        create_record(&context, &authority, &account, state_len).await;
+
+        let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+        let identity = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let (events, slot) = simulate_events(
+            &context,
+            &[
+                instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &config),
+                instruction::add_to_list(
+                    &account.pubkey(),
+                    &authority.pubkey(),
+                    &context.payer.pubkey(),
+                    &identity,
+                ),
+                instruction::set_signers(&account.pubkey(), &authority.pubkey(), 1, &[signer])
+                    .unwrap(),
+            ],
+            &[&authority],
+        )
+        .await;
+        let account_length = std::mem::size_of::<RecordData>() + state_len;
+        let event = |kind| Event {
+            record: account.pubkey(),
+            signer: authority.pubkey(),
+            slot,
+            kind,
+        };
+        assert_eq!(
+            events,
+            vec![
+                event(EventKind::Write {
+                    offset: 0,
+                    old_data: vec![0; state_len],
+                    new_data: bytemuck::bytes_of(&WeightingConfigState::new(config)).to_vec(),
+                }),
+                event(EventKind::Reallocate {
+                    old_len: account_length as u64,
+                    new_len: (account_length + std::mem::size_of::<Pubkey>()) as u64,
+                }),
+                event(EventKind::SignersChange {
+                    authority: authority.pubkey(),
+                    old_threshold: 0,
+                    old_signers: vec![],
+                    new_threshold: 1,
+                    new_signers: vec![signer],
+                }),
+            ]
+        );
+    }
+}
@@ -11,54 +11,40 @@
 //     system_instruction,
 //     transaction::Transaction,
 // };
+mod events;
+
 use {
     clap::{Parser, Subcommand},
-    gossip_weight_controller::instruction,
+    gossip_weight_controller::{
+        find_config_address, find_multiplier_table_address, find_override_address, instruction,
+    },
+    gossip_weight_controller_interface::{
+        self as interface,
+        state::{
+            resolve_config, BoundsData, HistoryEntry, ListMode, MultiplierEntry,
+            MultiplierTableData, ProposalData, RecordData, RecordDataV1, WeightingConfig,
+            WeightingConfigOverride, WeightingConfigState, WeightingMode,
+        },
+    },
+    num_traits::FromPrimitive,
     // log::info,
-    serde::Serialize,
     // solana_client::rpc_config::RpcSendTransactionConfig,
     solana_client::rpc_client::RpcClient,
     solana_commitment_config::CommitmentConfig,
-    solana_keypair::read_keypair_file,
+    solana_instruction::Instruction,
+    solana_keypair::{read_keypair_file, Keypair},
+    solana_program::{clock::Clock, sysvar},
     solana_pubkey::Pubkey,
+    solana_signature::Signature,
     solana_signer::Signer,
-    // solana_instruction::{AccountMeta, Instruction},
     solana_system_interface::instruction as system_instruction,
     solana_transaction::Transaction,
+    solana_transaction_status_client_types::{
+        option_serializer::OptionSerializer, UiTransactionEncoding,
+    },
+    std::str::FromStr,
 };
 
-const RECORD_META_DATA_SIZE: usize = 33;
-
-#[derive(Debug, Copy, Clone, Serialize)]
-#[repr(C)]
-pub struct WeightingConfig {
-    pub weighting_mode: u8, // 0 = Static, 1 = Dynamic
-    pub tc_ms: u64,         // IIR time constant in milliseconds
-    _future_use: [u8; 16],  // Reserved for future use
-}
-
-impl WeightingConfig {
-    pub fn new(weighting_mode: u8, tc_ms: u64) -> Self {
-        Self {
-            weighting_mode,
-            tc_ms,
-            _future_use: [0; 16],
-        }
-    }
-
-    fn as_bytes(&self) -> [u8; 9] {
-        let mut bytes = [0u8; 9];
-        bytes[0] = self.weighting_mode;
-        bytes[1..9].copy_from_slice(&self.tc_ms.to_le_bytes());
-        bytes
-    }
-}
-
-mod program_id {
-    // solana_program::declare_id!("5V1zhCNdTSe9Gaf38uuiJDHTpt1q6Gf3Yv7SMRk8SmwA");
-    solana_pubkey::declare_id!("recr1L3PCGKLbckBqMNcJhuuyU1zgo8nBhfLVsJNwr5");
-}
-
 #[derive(Parser)]
 #[command(name = "client")]
 struct Commandline {
@@ -83,6 +69,20 @@ struct Commandline {
     /// Set this pubkey as authority of account. This can be e.g. multisig pubkey
     authority_pubkey: Option<String>,
 
+    #[arg(long)]
+    /// Address the account by the authority and this profile name instead of
+    /// the storage account keypair
+    config_name: Option<String>,
+
+    #[arg(long, default_value = "0")]
+    /// Number of config changes the account created by `init` has room to
+    /// keep in its history, ignored with `--config-name`
+    history_capacity: u8,
+
+    #[arg(long = "signer-keypair")]
+    /// Keypair file of a signer from the account's signer set, may be repeated
+    signer_keypairs: Vec<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -92,9 +92,334 @@ enum Commands {
     /// Initialize account state
     Init {},
     /// Write to account
-    Write {},
+    Write {
+        #[arg(long)]
+        /// Only write if the account is still at this sequence number, see
+        /// `show`
+        expected_sequence: Option<u64>,
+    },
+    /// Print the account header and weighting config state
+    Show {},
+    /// Schedule the config to take effect at the start of an epoch
+    Schedule {
+        #[arg(long)]
+        /// First epoch in which the config is in effect
+        activation_epoch: u64,
+    },
+    /// Propose a new authority, which must accept before taking over
+    ProposeAuthority {
+        #[arg(long)]
+        /// Pubkey of the proposed authority
+        new_authority: String,
+    },
+    /// Accept a proposed authority transfer
+    AcceptAuthority {
+        #[arg(long)]
+        /// Keypair file of the proposed authority
+        new_authority_keypair: String,
+    },
+    /// Cancel a proposed authority transfer
+    CancelAuthorityTransfer {},
+    /// Replace the signer set approving changes to the account
+    SetSigners {
+        #[arg(long)]
+        /// Number of signers required to approve a change, 0 to hand control
+        /// back to the authority
+        threshold: u8,
+        #[arg(long = "signer")]
+        /// Pubkey of a signer in the new set, may be repeated
+        signers: Vec<String>,
+    },
+    /// Propose writing the config, to be approved by the signer set over
+    /// several transactions
+    CreateProposal {
+        #[arg(long)]
+        /// Keypair file of the new proposal account
+        proposal_keypair: String,
+        #[arg(long)]
+        /// Slot from which the proposal can no longer be approved or executed
+        expiry_slot: u64,
+    },
+    /// Approve a pending proposal
+    ApproveProposal {
+        #[arg(long)]
+        /// Pubkey of the proposal account
+        proposal: String,
+    },
+    /// Apply a proposal that has gathered enough approvals
+    ExecuteProposal {
+        #[arg(long)]
+        /// Pubkey of the proposal account
+        proposal: String,
+    },
+    /// Close a proposal and reclaim its rent
+    CancelProposal {
+        #[arg(long)]
+        /// Pubkey of the proposal account
+        proposal: String,
+    },
+    /// Keep a history of config changes, growing the account to make room
+    /// for it. The newest changes already recorded that fit are kept.
+    SetHistoryCapacity {
+        #[arg(long)]
+        /// Number of config changes to keep, 0 to stop keeping a history
+        capacity: u8,
+    },
+    /// Print the history of config changes, oldest first
+    History {},
+    /// Make the config active after an earlier change active again
+    Rollback {
+        #[arg(long, default_value = "1")]
+        /// Number of changes to go back from the latest one
+        versions_back: u8,
+    },
+    /// Permanently freeze the account, after which it can never change
+    Freeze {},
     /// Close the account
     Close {},
+    /// Override fields of the config for a single validator
+    SetOverride {
+        #[arg(long)]
+        /// Identity pubkey of the validator
+        identity: String,
+        #[arg(long)]
+        /// Weighting mode used by the validator instead of the config's
+        override_mode: Option<u8>,
+        #[arg(long)]
+        /// IIR time constant in milliseconds used by the validator instead of
+        /// the config's
+        override_tc_ms: Option<u64>,
+    },
+    /// Remove the override of a validator, which then follows the config
+    ClearOverride {
+        #[arg(long)]
+        /// Identity pubkey of the validator
+        identity: String,
+    },
+    /// Print the config in effect for a validator, with its override applied
+    ResolveConfig {
+        #[arg(long)]
+        /// Identity pubkey of the validator
+        identity: String,
+    },
+    /// Add a validator to the identity list
+    AddToList {
+        #[arg(long)]
+        /// Identity pubkey of the validator
+        identity: String,
+    },
+    /// Remove a validator from the identity list
+    RemoveFromList {
+        #[arg(long)]
+        /// Identity pubkey of the validator
+        identity: String,
+    },
+    /// Set how the identity list applies to dynamic weighting
+    SetListMode {
+        #[arg(long)]
+        /// List mode: 0 = Deny, listed validators are excluded, 1 = Allow,
+        /// only listed validators are included
+        mode: u8,
+    },
+    /// Create the stake multiplier table of the config
+    CreateMultiplierTable {
+        #[arg(long)]
+        /// Smallest multiplier accepted in the table, in basis points
+        min_bps: u32,
+        #[arg(long)]
+        /// Largest multiplier accepted in the table, in basis points
+        max_bps: u32,
+    },
+    /// Insert or update stake multipliers
+    SetMultipliers {
+        #[arg(long = "entry")]
+        /// Validator identity and multiplier in basis points, as
+        /// `IDENTITY=BPS`, may be repeated
+        entries: Vec<String>,
+    },
+    /// Delete the stake multipliers of the given validators
+    RemoveMultipliers {
+        #[arg(long = "identity")]
+        /// Identity pubkey of a validator, may be repeated
+        identities: Vec<String>,
+    },
+    /// Print the stake multiplier table
+    ShowMultipliers {},
+    /// Replace the range of multipliers accepted in the stake multiplier
+    /// table
+    SetMultiplierRange {
+        #[arg(long)]
+        /// Smallest multiplier accepted in the table, in basis points
+        min_bps: u32,
+        #[arg(long)]
+        /// Largest multiplier accepted in the table, in basis points
+        max_bps: u32,
+    },
+    /// Close the stake multiplier table, returning its rent to the payer
+    CloseMultiplierTable {},
+    /// Replace the writable data of the account with the contents of a file,
+    /// staged over as many transactions as needed and committed at once
+    StagedWrite {
+        #[arg(long)]
+        /// File holding the new data
+        data_file: String,
+        #[arg(long, default_value = "800")]
+        /// Number of bytes staged per transaction
+        chunk_size: usize,
+    },
+    /// Discard a staged write in progress
+    AbortStagedWrite {},
+    /// Allow a key to write the config, or stop it with `--remove`. A writer
+    /// runs `write` with its keypair as `--payer-keypair`
+    SetWriter {
+        #[arg(long)]
+        /// Writer pubkey
+        writer: String,
+        #[arg(long)]
+        /// Remove the writer instead of adding it
+        remove: bool,
+    },
+    /// Set the key allowed to force static weighting, omitted to remove it
+    SetEmergencyAuthority {
+        #[arg(long)]
+        /// Emergency authority pubkey
+        emergency_authority: Option<String>,
+    },
+    /// Force the config into static weighting, signed by the emergency
+    /// authority as `--payer-keypair`
+    EmergencyStatic {},
+    /// Set the number of slots that must pass between config changes
+    SetMinSlotsBetweenWrites {
+        #[arg(long)]
+        /// Minimum number of slots, zero for no limit
+        min_slots: u64,
+    },
+    /// Create a bounds account governed by the payer
+    CreateBounds {
+        #[arg(long)]
+        /// Keypair file of the new bounds account
+        bounds_keypair: String,
+        #[arg(long)]
+        /// Smallest accepted IIR time constant in milliseconds
+        min_tc_ms: u64,
+        #[arg(long)]
+        /// Largest accepted IIR time constant in milliseconds
+        max_tc_ms: u64,
+        #[arg(long, default_value = "3")]
+        /// Accepted weighting modes, bit `1 << mode` set for each
+        allowed_modes: u8,
+    },
+    /// Change a bounds account governed by the payer
+    UpdateBounds {
+        #[arg(long)]
+        /// Bounds account pubkey
+        bounds: String,
+        #[arg(long)]
+        /// Smallest accepted IIR time constant in milliseconds
+        min_tc_ms: u64,
+        #[arg(long)]
+        /// Largest accepted IIR time constant in milliseconds
+        max_tc_ms: u64,
+        #[arg(long, default_value = "3")]
+        /// Accepted weighting modes, bit `1 << mode` set for each
+        allowed_modes: u8,
+    },
+    /// Set the bounds account configs must fall within, omitted to remove it.
+    /// Removing or replacing bounds needs their governance authority as a
+    /// `--signer-keypair`
+    SetBounds {
+        #[arg(long)]
+        /// Bounds account pubkey
+        bounds: Option<String>,
+    },
+    /// Upgrade an account created with the version 1 header to the current
+    /// header
+    Migrate {},
+    /// Print the record changes logged by a transaction
+    Events {
+        #[arg(long)]
+        /// Transaction signature
+        signature: String,
+    },
+}
+
+/// Sign and send a transaction, reporting the outcome under `label`
+fn send_transaction(
+    client: &RpcClient,
+    payer_kp: &Keypair,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    label: &str,
+) {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer_kp.pubkey()));
+    transaction.sign(signers, client.get_latest_blockhash().unwrap());
+
+    // Send and confirm the transaction
+    match client.send_and_confirm_transaction(&transaction) {
+        Ok(signature) => println!("Transaction {label} Signature: {}", signature),
+        Err(err) => eprintln!("Error sending transaction: {}", err),
+    }
+}
+
+/// Sign and send a transaction gated by the record authority, or print it for
+/// an external authority such as a multisig to sign
+fn send_or_print_authority_transaction(
+    client: &RpcClient,
+    payer_kp: &Keypair,
+    signer_kps: &[Keypair],
+    instruction: Instruction,
+    external_authority: bool,
+    label: &str,
+) {
+    let signer_pubkeys = signer_kps.iter().map(|kp| kp.pubkey()).collect::<Vec<_>>();
+    let instruction = instruction::with_additional_signers(
+        instruction,
+        &signer_pubkeys.iter().collect::<Vec<_>>(),
+    );
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer_kp.pubkey()));
+    if !external_authority {
+        let mut signers = vec![payer_kp];
+        signers.extend(signer_kps);
+        transaction.sign(&signers, client.get_latest_blockhash().unwrap());
+
+        // Send and confirm the transaction
+        match client.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => println!("Transaction {label} Signature: {}", signature),
+            Err(err) => eprintln!("Error sending transaction: {}", err),
+        }
+    } else {
+        println!("Accounts: {:?}", transaction.message().account_keys);
+        println!(
+            "Instruction bytes base58:\n{}\n\n",
+            bs58::encode(transaction.data(0)).into_string()
+        );
+
+        println!("Instruction bytes raw:");
+        for b in transaction.data(0) {
+            print!("{b} ");
+        }
+        println!();
+    }
+}
+
+/// Append the bounds account of a record to an instruction writing its
+/// config, if the record references one
+fn with_record_bounds(
+    client: &RpcClient,
+    config_address: &Pubkey,
+    instruction: Instruction,
+) -> Instruction {
+    let account_data = client
+        .get_account_data(config_address)
+        .expect("Failed to fetch config account");
+    // records still at version 1 reference no bounds
+    match RecordData::unpack(&account_data)
+        .filter(|header| header.is_initialized())
+        .and_then(|header| header.bounds())
+    {
+        Some(bounds) => instruction::with_bounds(instruction, bounds),
+        None => instruction,
+    }
 }
 
 #[tokio::main]
@@ -104,12 +429,25 @@ async fn main() {
 
     let payer_kp =
         read_keypair_file(&cli.payer_keypair).expect("Failed to load config account keypair");
-    let storage_holder_kp =
-        read_keypair_file(&cli.storage_holder_kp).expect("Failed to load storage account keypair");
+    let authority_pubkey = if let Some(authority_pubkey) = &cli.authority_pubkey {
+        Pubkey::from_str_const(authority_pubkey)
+    } else {
+        payer_kp.pubkey()
+    };
+    let config_address = match &cli.config_name {
+        Some(config_name) => find_config_address(&authority_pubkey, config_name).0,
+        None => read_keypair_file(&cli.storage_holder_kp)
+            .expect("Failed to load storage account keypair")
+            .pubkey(),
+    };
+    let signer_kps = cli
+        .signer_keypairs
+        .iter()
+        .map(|path| read_keypair_file(path).expect("Failed to load signer keypair"))
+        .collect::<Vec<_>>();
 
     // === Create config account if needed ===
-    let record_size = std::mem::size_of::<WeightingConfig>();
-    let account_size = RECORD_META_DATA_SIZE + record_size;
+    let account_size = RecordData::history_end_index_for(cli.history_capacity);
     let lamports = client
         .get_minimum_balance_for_rent_exemption(account_size)
         .unwrap();
@@ -119,12 +457,32 @@ async fn main() {
 
     match cli.command {
         Commands::Init {} => {
+            if let Some(config_name) = &cli.config_name {
+                let instruction_create =
+                    instruction::create_config(&authority_pubkey, &payer_kp.pubkey(), config_name)
+                        .expect("Config name too long");
+                let mut transaction =
+                    Transaction::new_with_payer(&[instruction_create], Some(&payer_kp.pubkey()));
+                transaction.sign(&[&payer_kp], recent_blockhash);
+
+                match client.send_and_confirm_transaction(&transaction) {
+                    Ok(signature) => println!(
+                        "Config {} created Transaction Signature: {}",
+                        config_address, signature
+                    ),
+                    Err(err) => eprintln!("Error sending CreateConfig transaction: {}", err),
+                }
+                return;
+            }
+
+            let storage_holder_kp = read_keypair_file(&cli.storage_holder_kp)
+                .expect("Failed to load storage account keypair");
             let create_account_instruction = system_instruction::create_account(
                 &payer_kp.pubkey(),
                 &storage_holder_kp.pubkey(),
                 lamports,
                 account_size as u64,
-                &program_id::ID,
+                &interface::id(),
             );
             let mut create_account = Transaction::new_with_payer(
                 &[create_account_instruction],
@@ -137,14 +495,8 @@ async fn main() {
                 Err(err) => eprintln!("Error sending Account create transaction: {}", err),
             }
 
-            let authority_pubkey = if let Some(authority_pubkey) = cli.authority_pubkey {
-                Pubkey::from_str_const(&authority_pubkey)
-            } else {
-                payer_kp.pubkey()
-            };
             // Create the instruction to init the account
-            let instruction_init =
-                instruction::initialize(&storage_holder_kp.pubkey(), &authority_pubkey);
+            let instruction_init = instruction::initialize(&config_address, &authority_pubkey);
 
             let mut transaction =
                 Transaction::new_with_payer(&[instruction_init], Some(&payer_kp.pubkey()));
@@ -156,53 +508,810 @@ async fn main() {
                 Err(err) => eprintln!("Error sending Init transaction: {}", err),
             }
         }
-        Commands::Write {} => {
-            // send instruction to write number into account
-            let initial = WeightingConfig::new(cli.weighting_mode, cli.tc_ms);
-            let instruction_write = instruction::write(
-                &storage_holder_kp.pubkey(),
+        Commands::Write { expected_sequence } => {
+            // send instruction to write the config into account
+            let weighting_mode =
+                WeightingMode::from_u8(cli.weighting_mode).expect("Invalid weighting mode");
+            let config = WeightingConfig::new(weighting_mode, cli.tc_ms);
+            let instruction_write = match expected_sequence {
+                Some(expected_sequence) => instruction::write_if_sequence(
+                    &config_address,
+                    &payer_kp.pubkey(),
+                    expected_sequence,
+                    0,
+                    bytemuck::bytes_of(&WeightingConfigState::new(config)),
+                )
+                .expect("Data too long"),
+                None => {
+                    instruction::set_weighting_config(&config_address, &payer_kp.pubkey(), &config)
+                }
+            };
+            let instruction_write = with_record_bounds(&client, &config_address, instruction_write);
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_write,
+                cli.authority_pubkey.is_some(),
+                "Write",
+            );
+        }
+        Commands::Show {} => {
+            let account_data = client
+                .get_account_data(&config_address)
+                .expect("Failed to fetch config account");
+            if let Some(v1_header) = RecordDataV1::unpack(&account_data) {
+                println!("authority {}", v1_header.authority);
+                println!("version {}, run `migrate` to upgrade", v1_header.version);
+                return;
+            }
+            let header = RecordData::unpack(&account_data).expect("Account is not a record");
+            println!("authority {}", header.authority);
+            for writer in header.writers() {
+                println!("writer {}", writer);
+            }
+            if let Some(emergency_authority) = header.emergency_authority() {
+                println!("emergency authority {}", emergency_authority);
+            }
+            if let Some((triggered_by, slot)) = header.emergency_trigger() {
+                println!("emergency static by {} at slot {}", triggered_by, slot);
+            }
+            println!("sequence {}", u64::from(header.sequence));
+            println!(
+                "last write slot {} next write slot {}",
+                u64::from(header.last_write_slot),
+                header.next_write_slot()
+            );
+            println!("frozen {}", header.is_frozen());
+            if let Some(bounds) = header.bounds() {
+                println!("bounds {}", bounds);
+                if let Some(bounds_data) = client
+                    .get_account_data(bounds)
+                    .ok()
+                    .as_deref()
+                    .and_then(BoundsData::unpack)
+                {
+                    println!(
+                        "  tc_ms {}..={} modes {:#04b} governed by {}",
+                        u64::from(bounds_data.min_tc_ms),
+                        u64::from(bounds_data.max_tc_ms),
+                        bounds_data.allowed_modes,
+                        bounds_data.authority
+                    );
+                }
+            }
+            println!(
+                "complete {} verified {}",
+                header.is_complete(),
+                RecordData::unpack_verified(&account_data).is_some()
+            );
+            if let Some(state) = WeightingConfigState::unpack_from_record(&account_data) {
+                println!("{:?}", state);
+            }
+            if let Some(list) = RecordData::unpack_identity_list(&account_data) {
+                println!("list mode {:?}", header.list_mode());
+                for identity in list {
+                    println!("  {}", identity);
+                }
+            }
+        }
+        Commands::Schedule { activation_epoch } => {
+            let weighting_mode =
+                WeightingMode::from_u8(cli.weighting_mode).expect("Invalid weighting mode");
+            let config = WeightingConfig::new(weighting_mode, cli.tc_ms);
+            let instruction_schedule = instruction::schedule_weighting_config(
+                &config_address,
                 &payer_kp.pubkey(),
-                0,
-                &initial.as_bytes(),
+                &config,
+                activation_epoch,
             );
+            let instruction_schedule =
+                with_record_bounds(&client, &config_address, instruction_schedule);
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_schedule,
+                cli.authority_pubkey.is_some(),
+                "Schedule",
+            );
+        }
+        Commands::ProposeAuthority { new_authority } => {
+            let new_authority =
+                Pubkey::from_str(&new_authority).expect("Invalid new authority pubkey");
+            let instruction_propose =
+                instruction::propose_authority(&config_address, &payer_kp.pubkey(), &new_authority);
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_propose,
+                cli.authority_pubkey.is_some(),
+                "ProposeAuthority",
+            );
+        }
+        Commands::AcceptAuthority {
+            new_authority_keypair,
+        } => {
+            let new_authority_kp = read_keypair_file(&new_authority_keypair)
+                .expect("Failed to load new authority keypair");
+            let instruction_accept =
+                instruction::accept_authority(&config_address, &new_authority_kp.pubkey());
             let mut transaction =
-                Transaction::new_with_payer(&[instruction_write], Some(&payer_kp.pubkey()));
-            if cli.authority_pubkey.is_none() {
-                transaction.sign(&[&payer_kp], client.get_latest_blockhash().unwrap());
+                Transaction::new_with_payer(&[instruction_accept], Some(&payer_kp.pubkey()));
+            transaction.sign(
+                &[&payer_kp, &new_authority_kp],
+                client.get_latest_blockhash().unwrap(),
+            );
 
-                // Send and confirm the transaction
-                match client.send_and_confirm_transaction(&transaction) {
-                    Ok(signature) => println!("Transaction Write Signature: {}", signature),
-                    Err(err) => eprintln!("Error sending transaction: {}", err),
-                }
-            } else {
-                println!("Accounts: {:?}", transaction.message().account_keys);
+            // Send and confirm the transaction
+            match client.send_and_confirm_transaction(&transaction) {
+                Ok(signature) => println!("Transaction AcceptAuthority Signature: {}", signature),
+                Err(err) => eprintln!("Error sending transaction: {}", err),
+            }
+        }
+        Commands::CancelAuthorityTransfer {} => {
+            let instruction_cancel =
+                instruction::cancel_authority_transfer(&config_address, &payer_kp.pubkey());
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_cancel,
+                cli.authority_pubkey.is_some(),
+                "CancelAuthorityTransfer",
+            );
+        }
+        Commands::SetSigners { threshold, signers } => {
+            let signers = signers
+                .iter()
+                .map(|signer| Pubkey::from_str(signer).expect("Invalid signer pubkey"))
+                .collect::<Vec<_>>();
+            let instruction_set_signers =
+                instruction::set_signers(&config_address, &payer_kp.pubkey(), threshold, &signers)
+                    .expect("Too many signers");
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_signers,
+                cli.authority_pubkey.is_some(),
+                "SetSigners",
+            );
+        }
+        Commands::CreateProposal {
+            proposal_keypair,
+            expiry_slot,
+        } => {
+            let proposal_kp =
+                read_keypair_file(&proposal_keypair).expect("Failed to load proposal keypair");
+            let weighting_mode =
+                WeightingMode::from_u8(cli.weighting_mode).expect("Invalid weighting mode");
+            let config = WeightingConfig::new(weighting_mode, cli.tc_ms);
+            let data = bytemuck::bytes_of(&config);
+            let proposal_size = ProposalData::DATA_START_INDEX + data.len();
+            let proposal_lamports = client
+                .get_minimum_balance_for_rent_exemption(proposal_size)
+                .unwrap();
+            let instructions = [
+                system_instruction::create_account(
+                    &payer_kp.pubkey(),
+                    &proposal_kp.pubkey(),
+                    proposal_lamports,
+                    proposal_size as u64,
+                    &interface::id(),
+                ),
+                instruction::create_proposal(
+                    &proposal_kp.pubkey(),
+                    &config_address,
+                    &payer_kp.pubkey(),
+                    expiry_slot,
+                    0,
+                    data,
+                )
+                .expect("Data too long"),
+            ];
+            send_transaction(
+                &client,
+                &payer_kp,
+                &instructions,
+                &[&payer_kp, &proposal_kp],
+                "CreateProposal",
+            );
+        }
+        Commands::ApproveProposal { proposal } => {
+            let proposal = Pubkey::from_str(&proposal).expect("Invalid proposal pubkey");
+            let instruction_approve =
+                instruction::approve_proposal(&proposal, &config_address, &payer_kp.pubkey());
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_approve],
+                &[&payer_kp],
+                "ApproveProposal",
+            );
+        }
+        Commands::ExecuteProposal { proposal } => {
+            let proposal = Pubkey::from_str(&proposal).expect("Invalid proposal pubkey");
+            let instruction_execute = with_record_bounds(
+                &client,
+                &config_address,
+                instruction::execute_proposal(&proposal, &config_address),
+            );
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_execute],
+                &[&payer_kp],
+                "ExecuteProposal",
+            );
+        }
+        Commands::CancelProposal { proposal } => {
+            let proposal = Pubkey::from_str(&proposal).expect("Invalid proposal pubkey");
+            let instruction_cancel =
+                instruction::cancel_proposal(&proposal, &payer_kp.pubkey(), &payer_kp.pubkey());
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_cancel],
+                &[&payer_kp],
+                "CancelProposal",
+            );
+        }
+        Commands::SetHistoryCapacity { capacity } => {
+            let instruction_set_history_capacity = instruction::set_history_capacity(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                capacity,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_history_capacity,
+                cli.authority_pubkey.is_some(),
+                "SetHistoryCapacity",
+            );
+        }
+        Commands::History {} => {
+            let account_data = client
+                .get_account_data(&config_address)
+                .expect("Failed to fetch config account");
+            let history = HistoryEntry::unpack_history(&account_data)
+                .expect("Account does not hold a weighting config history");
+            for entry in history {
+                let active = &entry.state.active;
                 println!(
-                    "Instruction bytes base58:\n{}\n\n",
-                    bs58::encode(transaction.data(0)).into_string()
+                    "slot {} signer {} mode {:?} tc_ms {} pending {:?}",
+                    u64::from(entry.slot),
+                    entry.signer,
+                    active.weighting_mode(),
+                    u64::from(active.tc_ms),
+                    entry.state.activation_epoch().map(|epoch| (
+                        epoch,
+                        entry.state.pending.weighting_mode(),
+                        u64::from(entry.state.pending.tc_ms)
+                    )),
                 );
-
-                println!("Instruction bytes raw:");
-                for b in transaction.data(0) {
-                    print!("{b} ");
-                }
-                println!();
             }
         }
+        Commands::Rollback { versions_back } => {
+            let instruction_rollback = with_record_bounds(
+                &client,
+                &config_address,
+                instruction::rollback(&config_address, &payer_kp.pubkey(), versions_back),
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_rollback,
+                cli.authority_pubkey.is_some(),
+                "Rollback",
+            );
+        }
+        Commands::Freeze {} => {
+            let instruction_freeze = instruction::freeze(&config_address, &payer_kp.pubkey());
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_freeze,
+                cli.authority_pubkey.is_some(),
+                "Freeze",
+            );
+        }
         Commands::Close {} => {
-            let instruction_close = instruction::close_account(
-                &storage_holder_kp.pubkey(),
+            let instruction_close =
+                instruction::close_account(&config_address, &payer_kp.pubkey(), &payer_kp.pubkey());
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_close,
+                cli.authority_pubkey.is_some(),
+                "Close",
+            );
+        }
+        Commands::SetOverride {
+            identity,
+            override_mode,
+            override_tc_ms,
+        } => {
+            let identity = Pubkey::from_str(&identity).expect("Invalid identity pubkey");
+            let mut overrides = WeightingConfigOverride::default();
+            if let Some(override_mode) = override_mode {
+                let weighting_mode =
+                    WeightingMode::from_u8(override_mode).expect("Invalid weighting mode");
+                overrides.fields |= WeightingConfigOverride::WEIGHTING_MODE;
+                overrides.config.weighting_mode = weighting_mode as u8;
+            }
+            if let Some(override_tc_ms) = override_tc_ms {
+                overrides.fields |= WeightingConfigOverride::TC_MS;
+                overrides.config.tc_ms = override_tc_ms.into();
+            }
+            let instruction_set_override = with_record_bounds(
+                &client,
+                &config_address,
+                instruction::set_override(
+                    &config_address,
+                    &payer_kp.pubkey(),
+                    &payer_kp.pubkey(),
+                    &identity,
+                    &overrides,
+                ),
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_override,
+                cli.authority_pubkey.is_some(),
+                "SetOverride",
+            );
+        }
+        Commands::ClearOverride { identity } => {
+            let identity = Pubkey::from_str(&identity).expect("Invalid identity pubkey");
+            let instruction_clear_override = instruction::clear_override(
+                &config_address,
                 &payer_kp.pubkey(),
                 &payer_kp.pubkey(),
+                &identity,
             );
-            let mut transaction =
-                Transaction::new_with_payer(&[instruction_close], Some(&payer_kp.pubkey()));
-            transaction.sign(&[&payer_kp], client.get_latest_blockhash().unwrap());
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_clear_override,
+                cli.authority_pubkey.is_some(),
+                "ClearOverride",
+            );
+        }
+        Commands::ResolveConfig { identity } => {
+            let identity = Pubkey::from_str(&identity).expect("Invalid identity pubkey");
+            let (override_address, _) = find_override_address(&config_address, &identity);
+            let mut accounts = client
+                .get_multiple_accounts(&[config_address, override_address, sysvar::clock::id()])
+                .expect("Failed to fetch accounts")
+                .into_iter();
+            let config_account = accounts.next().flatten().expect("Config account not found");
+            let override_account = accounts.next().flatten();
+            let clock_account = accounts.next().flatten().expect("Clock sysvar not found");
+            let clock: Clock =
+                bincode::deserialize(&clock_account.data).expect("Invalid clock sysvar");
+            let config = resolve_config(
+                &config_address,
+                &config_account.data,
+                &identity,
+                override_account
+                    .as_ref()
+                    .map(|account| account.data.as_slice()),
+                &clock,
+            )
+            .expect("Account does not hold a complete weighting config");
+            println!(
+                "mode {:?} tc_ms {} overridden {}",
+                config.weighting_mode(),
+                u64::from(config.tc_ms),
+                override_account.is_some(),
+            );
+        }
+        Commands::AddToList { identity } => {
+            let identity = Pubkey::from_str(&identity).expect("Invalid identity pubkey");
+            let instruction_add = instruction::add_to_list(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                &identity,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_add,
+                cli.authority_pubkey.is_some(),
+                "AddToList",
+            );
+        }
+        Commands::RemoveFromList { identity } => {
+            let identity = Pubkey::from_str(&identity).expect("Invalid identity pubkey");
+            let instruction_remove = instruction::remove_from_list(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                &identity,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_remove,
+                cli.authority_pubkey.is_some(),
+                "RemoveFromList",
+            );
+        }
+        Commands::SetListMode { mode } => {
+            let mode = ListMode::from_u8(mode).expect("Invalid list mode");
+            let instruction_set_list_mode =
+                instruction::set_list_mode(&config_address, &payer_kp.pubkey(), mode);
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_list_mode,
+                cli.authority_pubkey.is_some(),
+                "SetListMode",
+            );
+        }
+        Commands::CreateMultiplierTable { min_bps, max_bps } => {
+            let instruction_create = instruction::create_multiplier_table(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                min_bps,
+                max_bps,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_create,
+                cli.authority_pubkey.is_some(),
+                "CreateMultiplierTable",
+            );
+        }
+        Commands::SetMultipliers { entries } => {
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    let (identity, multiplier_bps) =
+                        entry.split_once('=').expect("Entry must be IDENTITY=BPS");
+                    MultiplierEntry::new(
+                        Pubkey::from_str(identity).expect("Invalid identity pubkey"),
+                        multiplier_bps.parse().expect("Invalid multiplier"),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let instruction_set = instruction::set_multipliers(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                &entries,
+            )
+            .expect("Too many multiplier entries");
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set,
+                cli.authority_pubkey.is_some(),
+                "SetMultipliers",
+            );
+        }
+        Commands::RemoveMultipliers { identities } => {
+            let identities = identities
+                .iter()
+                .map(|identity| Pubkey::from_str(identity).expect("Invalid identity pubkey"))
+                .collect::<Vec<_>>();
+            let instruction_remove = instruction::remove_multipliers(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                &identities,
+            )
+            .expect("Too many identities");
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_remove,
+                cli.authority_pubkey.is_some(),
+                "RemoveMultipliers",
+            );
+        }
+        Commands::SetMultiplierRange { min_bps, max_bps } => {
+            let instruction_set_range = instruction::set_multiplier_range(
+                &config_address,
+                &payer_kp.pubkey(),
+                min_bps,
+                max_bps,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_range,
+                cli.authority_pubkey.is_some(),
+                "SetMultiplierRange",
+            );
+        }
+        Commands::CloseMultiplierTable {} => {
+            let instruction_close = instruction::close_multiplier_table(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_close,
+                cli.authority_pubkey.is_some(),
+                "CloseMultiplierTable",
+            );
+        }
+        Commands::ShowMultipliers {} => {
+            let (table_address, _) = find_multiplier_table_address(&config_address);
+            let account_data = client
+                .get_account_data(&table_address)
+                .expect("Failed to fetch multiplier table");
+            let (table_data, entries) =
+                MultiplierTableData::unpack(&account_data).expect("Account is not a table");
+            println!(
+                "range {}..={} bps",
+                u32::from(table_data.min_multiplier_bps),
+                u32::from(table_data.max_multiplier_bps)
+            );
+            for entry in entries {
+                println!("{} {}", entry.identity, u32::from(entry.multiplier_bps));
+            }
+        }
+        Commands::StagedWrite {
+            data_file,
+            chunk_size,
+        } => {
+            let data = std::fs::read(&data_file).expect("Failed to read data file");
 
-            // Send and confirm the transaction
-            match client.send_and_confirm_transaction(&transaction) {
-                Ok(signature) => println!("Transaction Close Signature: {}", signature),
-                Err(err) => eprintln!("Error sending transaction: {}", err),
+            // the staged data starts out as the current writable data
+            let account_data = client
+                .get_account_data(&config_address)
+                .expect("Failed to fetch config account");
+            let header = RecordData::unpack(&account_data).expect("Account is not a record");
+            let payload = &account_data[RecordData::WRITABLE_START_INDEX..];
+            let mut staged_data = payload[..header.writable_len(payload.len())].to_vec();
+            staged_data
+                .get_mut(..data.len())
+                .expect("Data does not fit the account")
+                .copy_from_slice(&data);
+            let mut instructions = vec![instruction::begin_staged_write(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+            )];
+            let chunk_size = chunk_size.max(1);
+            for (index, chunk) in data.chunks(chunk_size).enumerate() {
+                instructions.push(
+                    instruction::stage_chunk(
+                        &config_address,
+                        &payer_kp.pubkey(),
+                        (index * chunk_size) as u64,
+                        chunk,
+                    )
+                    .expect("Chunk too long"),
+                );
+            }
+            let instruction_commit = instruction::commit_staged_write(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+                RecordData::compute_content_hash(&staged_data),
+            );
+            instructions.push(match header.bounds() {
+                Some(bounds) => instruction::with_bounds(instruction_commit, bounds),
+                None => instruction_commit,
+            });
+            for instruction_staged in instructions {
+                send_or_print_authority_transaction(
+                    &client,
+                    &payer_kp,
+                    &signer_kps,
+                    instruction_staged,
+                    cli.authority_pubkey.is_some(),
+                    "StagedWrite",
+                );
+            }
+        }
+        Commands::AbortStagedWrite {} => {
+            let instruction_abort = instruction::abort_staged_write(
+                &config_address,
+                &payer_kp.pubkey(),
+                &payer_kp.pubkey(),
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_abort,
+                cli.authority_pubkey.is_some(),
+                "AbortStagedWrite",
+            );
+        }
+        Commands::SetWriter { writer, remove } => {
+            let writer = Pubkey::from_str(&writer).expect("Invalid writer pubkey");
+            let instruction_set_writer =
+                instruction::set_writer(&config_address, &payer_kp.pubkey(), &writer, !remove);
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_writer,
+                cli.authority_pubkey.is_some(),
+                "SetWriter",
+            );
+        }
+        Commands::SetEmergencyAuthority {
+            emergency_authority,
+        } => {
+            let emergency_authority = emergency_authority
+                .map(|key| Pubkey::from_str(&key).expect("Invalid emergency authority pubkey"))
+                .unwrap_or_default();
+            let instruction_set_emergency_authority = instruction::set_emergency_authority(
+                &config_address,
+                &payer_kp.pubkey(),
+                &emergency_authority,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_emergency_authority,
+                cli.authority_pubkey.is_some(),
+                "SetEmergencyAuthority",
+            );
+        }
+        Commands::EmergencyStatic {} => {
+            let instruction_emergency_static =
+                instruction::emergency_static(&config_address, &payer_kp.pubkey());
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_emergency_static],
+                &[&payer_kp],
+                "EmergencyStatic",
+            );
+        }
+        Commands::SetMinSlotsBetweenWrites { min_slots } => {
+            let instruction_set_min_slots = instruction::set_min_slots_between_writes(
+                &config_address,
+                &payer_kp.pubkey(),
+                min_slots,
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_min_slots,
+                cli.authority_pubkey.is_some(),
+                "SetMinSlotsBetweenWrites",
+            );
+        }
+        Commands::CreateBounds {
+            bounds_keypair,
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        } => {
+            let bounds_kp =
+                read_keypair_file(&bounds_keypair).expect("Failed to load bounds account keypair");
+            let bounds_size = std::mem::size_of::<BoundsData>();
+            let bounds_lamports = client
+                .get_minimum_balance_for_rent_exemption(bounds_size)
+                .unwrap();
+            let instructions = [
+                system_instruction::create_account(
+                    &payer_kp.pubkey(),
+                    &bounds_kp.pubkey(),
+                    bounds_lamports,
+                    bounds_size as u64,
+                    &interface::id(),
+                ),
+                instruction::create_bounds(
+                    &bounds_kp.pubkey(),
+                    &payer_kp.pubkey(),
+                    min_tc_ms,
+                    max_tc_ms,
+                    allowed_modes,
+                ),
+            ];
+            send_transaction(
+                &client,
+                &payer_kp,
+                &instructions,
+                &[&payer_kp, &bounds_kp],
+                "CreateBounds",
+            );
+        }
+        Commands::UpdateBounds {
+            bounds,
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        } => {
+            let bounds = Pubkey::from_str(&bounds).expect("Invalid bounds pubkey");
+            let instruction_update_bounds = instruction::update_bounds(
+                &bounds,
+                &payer_kp.pubkey(),
+                min_tc_ms,
+                max_tc_ms,
+                allowed_modes,
+            );
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_update_bounds],
+                &[&payer_kp],
+                "UpdateBounds",
+            );
+        }
+        Commands::SetBounds { bounds } => {
+            let bounds = bounds
+                .map(|key| Pubkey::from_str(&key).expect("Invalid bounds pubkey"))
+                .unwrap_or_default();
+            // the current bounds let their governance authority approve
+            let instruction_set_bounds = with_record_bounds(
+                &client,
+                &config_address,
+                instruction::set_bounds(&config_address, &payer_kp.pubkey(), &bounds),
+            );
+            send_or_print_authority_transaction(
+                &client,
+                &payer_kp,
+                &signer_kps,
+                instruction_set_bounds,
+                cli.authority_pubkey.is_some(),
+                "SetBounds",
+            );
+        }
+        Commands::Migrate {} => {
+            let instruction_migrate =
+                instruction::migrate(&config_address, &payer_kp.pubkey(), &payer_kp.pubkey());
+            send_transaction(
+                &client,
+                &payer_kp,
+                &[instruction_migrate],
+                &[&payer_kp],
+                "Migrate",
+            );
+        }
+        Commands::Events { signature } => {
+            let signature = Signature::from_str(&signature).expect("Invalid signature");
+            let transaction = client
+                .get_transaction(&signature, UiTransactionEncoding::Json)
+                .expect("Failed to fetch transaction");
+            let logs = match transaction.transaction.meta.map(|meta| meta.log_messages) {
+                Some(OptionSerializer::Some(logs)) => logs,
+                _ => {
+                    println!("Transaction has no logs");
+                    return;
+                }
+            };
+            let events = events::decode_events(&logs);
+            if events.is_empty() {
+                println!("No record events in transaction");
+            }
+            for event in events {
+                println!("{event}");
             }
         }
     }
@@ -0,0 +1,164 @@
+//! Events logged by the program for indexers
+//!
+//! Every change to a record is logged with `sol_log_data` as a list of data
+//! fields. The first holds an [`EventHeader`] and the second the body given by
+//! its [`EventType`]. [`EventType::Write`] is followed by a field with the
+//! bytes overwritten and one with the bytes written, and
+//! [`EventType::SignersChange`] by a field with the old and one with the new
+//! signers.
+use {
+    crate::pod::PodU64,
+    bytemuck::{Pod, Zeroable},
+    num_derive::FromPrimitive,
+    num_traits::FromPrimitive,
+    solana_pubkey::Pubkey,
+};
+
+/// Kind of change an event records, which gives the layout of its body
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum EventType {
+    /// A record was initialized, see [`InitializeEvent`]
+    Initialize = 0,
+    /// Bytes of a record were written, see [`WriteEvent`]
+    Write = 1,
+    /// The authority of a record changed, see [`AuthorityChangeEvent`]
+    AuthorityChange = 2,
+    /// A record was closed, see [`CloseEvent`]
+    Close = 3,
+    /// A record was resized, see [`ReallocateEvent`]
+    Reallocate = 4,
+    /// The signers approving for the authority of a record changed, see
+    /// [`SignersChangeEvent`]
+    SignersChange = 5,
+}
+
+/// Fields common to every event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct EventHeader {
+    /// Kind of change, see [`EventType`]
+    pub event_type: u8,
+
+    /// Record account that changed
+    pub record: Pubkey,
+
+    /// Account that approved the change, or the default pubkey if the change
+    /// needed no signature
+    pub signer: Pubkey,
+
+    /// Slot in which the change was made
+    pub slot: PodU64,
+}
+
+impl EventHeader {
+    /// Get the kind of change, if it is known
+    pub fn event_type(&self) -> Option<EventType> {
+        EventType::from_u8(self.event_type)
+    }
+}
+
+/// Body of an [`EventType::Initialize`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct InitializeEvent {
+    /// Authority of the new record
+    pub authority: Pubkey,
+}
+
+/// Body of an [`EventType::Write`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct WriteEvent {
+    /// Offset of the bytes written in the writable data of the record
+    pub offset: PodU64,
+}
+
+/// Body of an [`EventType::AuthorityChange`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct AuthorityChangeEvent {
+    /// Authority before the change
+    pub old_authority: Pubkey,
+
+    /// Authority after the change
+    pub new_authority: Pubkey,
+}
+
+/// Body of an [`EventType::Close`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct CloseEvent {
+    /// Receiver of the record's lamports
+    pub destination: Pubkey,
+
+    /// Lamports the record held
+    pub lamports: PodU64,
+}
+
+/// Body of an [`EventType::Reallocate`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ReallocateEvent {
+    /// Length of the account data before the change
+    pub old_len: PodU64,
+
+    /// Length of the account data after the change
+    pub new_len: PodU64,
+}
+
+/// Body of an [`EventType::SignersChange`] event
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct SignersChangeEvent {
+    /// Authority the signers approve for
+    pub authority: Pubkey,
+
+    /// Number of signers required before the change
+    pub old_threshold: u8,
+
+    /// Number of signers required after the change
+    pub new_threshold: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_event_header() {
+        let header = EventHeader {
+            event_type: EventType::AuthorityChange as u8,
+            record: Pubkey::new_from_array([1; 32]),
+            signer: Pubkey::new_from_array([2; 32]),
+            slot: 42.into(),
+        };
+        let mut expected = vec![EventType::AuthorityChange as u8];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&[2; 32]);
+        expected.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(bytemuck::bytes_of(&header), expected);
+        assert_eq!(header.event_type(), Some(EventType::AuthorityChange));
+    }
+
+    #[test]
+    fn serialize_signers_change_event() {
+        let event = SignersChangeEvent {
+            authority: Pubkey::new_from_array([3; 32]),
+            old_threshold: 1,
+            new_threshold: 2,
+        };
+        let mut expected = vec![3; 32];
+        expected.extend_from_slice(&[1, 2]);
+        assert_eq!(bytemuck::bytes_of(&event), expected);
+    }
+
+    #[test]
+    fn unknown_event_type() {
+        let header = EventHeader {
+            event_type: 6,
+            ..EventHeader::zeroed()
+        };
+        assert_eq!(header.event_type(), None);
+    }
+}
@@ -0,0 +1,14 @@
+//! Account layouts shared by the gossip weight controller program, its
+//! clients and validator-side readers
+#![deny(missing_docs)]
+#![cfg_attr(not(test), no_std)]
+
+pub mod event;
+pub mod pod;
+pub mod state;
+
+// Export current SDK types for downstream users building with a different SDK
+// version
+pub use solana_pubkey;
+
+solana_pubkey::declare_id!("recr1L3PCGKLbckBqMNcJhuuyU1zgo8nBhfLVsJNwr5");
@@ -0,0 +1,58 @@
+//! Integer types with an alignment of 1, for use in account data
+
+use bytemuck::{Pod, Zeroable};
+
+/// `u64` stored as little-endian bytes
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Pod, Zeroable)]
+pub struct PodU64(pub [u8; 8]);
+
+impl From<u64> for PodU64 {
+    fn from(n: u64) -> Self {
+        Self(n.to_le_bytes())
+    }
+}
+
+impl From<PodU64> for u64 {
+    fn from(pod: PodU64) -> Self {
+        Self::from_le_bytes(pod.0)
+    }
+}
+
+/// `u32` stored as little-endian bytes
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Pod, Zeroable)]
+pub struct PodU32(pub [u8; 4]);
+
+impl From<u32> for PodU32 {
+    fn from(n: u32) -> Self {
+        Self(n.to_le_bytes())
+    }
+}
+
+impl From<PodU32> for u32 {
+    fn from(pod: PodU32) -> Self {
+        Self::from_le_bytes(pod.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pod_u64_round_trip() {
+        let pod = PodU64::from(0x0102_0304_0506_0708);
+        assert_eq!(pod.0, [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(u64::from(pod), 0x0102_0304_0506_0708);
+        assert_eq!(core::mem::align_of::<PodU64>(), 1);
+    }
+
+    #[test]
+    fn pod_u32_round_trip() {
+        let pod = PodU32::from(0x0102_0304);
+        assert_eq!(pod.0, [4, 3, 2, 1]);
+        assert_eq!(u32::from(pod), 0x0102_0304);
+        assert_eq!(core::mem::align_of::<PodU32>(), 1);
+    }
+}
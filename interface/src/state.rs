@@ -0,0 +1,1447 @@
+//! Program state
+use {
+    crate::pod::{PodU32, PodU64},
+    bytemuck::{Pod, Zeroable},
+    num_derive::FromPrimitive,
+    num_traits::FromPrimitive,
+    solana_clock::{Clock, Epoch, Slot},
+    solana_pubkey::Pubkey,
+};
+
+/// Header type for recorded account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct RecordData {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// The account allowed to update the data
+    pub authority: Pubkey,
+
+    /// Layout of the data following the header, see [`RecordSchema`]
+    pub schema: u8,
+
+    /// Proposed new authority that has yet to accept the transfer, or the
+    /// default pubkey if no transfer is in progress
+    pub pending_authority: Pubkey,
+
+    /// Signer set that replaces `authority` while `threshold` is non-zero,
+    /// only the first `num_signers` entries are used
+    pub signers: [Pubkey; RecordData::MAX_SIGNERS],
+
+    /// Number of entries in use in `signers`
+    pub num_signers: u8,
+
+    /// Number of listed signers required to approve a change, or zero if
+    /// `authority` alone approves changes
+    pub threshold: u8,
+
+    /// Number of [`HistoryEntry`] slots following the weighting config
+    /// state, or zero if changes are not recorded
+    pub history_capacity: u8,
+
+    /// Number of changes recorded since the history was enabled, the next
+    /// change overwrites the oldest entry once the history is full
+    pub history_count: PodU64,
+
+    /// Number of changes made to the record since it was initialized, for
+    /// writers to detect changes made concurrently with theirs
+    pub sequence: PodU64,
+
+    /// Non-zero once the record is frozen, after which it can never change
+    pub frozen: u8,
+
+    /// How the identity list following the history applies to dynamic
+    /// weighting, see [`ListMode`]
+    pub list_mode: u8,
+
+    /// Number of validator identities in the identity list, which is kept
+    /// sorted
+    pub list_len: PodU64,
+
+    /// Non-zero while the data is a finished config, cleared by writers
+    /// spreading a change over several transactions until they are done
+    pub complete: u8,
+
+    /// SHA-256 hash of the data following the header, updated by the program
+    /// after every change
+    pub content_hash: [u8; 32],
+
+    /// Keys allowed to write the config alongside the authority, which
+    /// remains the admin managing roles, only the first `num_writers`
+    /// entries are used
+    pub writers: [Pubkey; RecordData::MAX_WRITERS],
+
+    /// Number of entries in use in `writers`
+    pub num_writers: u8,
+
+    /// Key allowed to force the config into static weighting without the
+    /// authority, or the default pubkey if there is none
+    pub emergency_authority: Pubkey,
+
+    /// Key that last forced the config into static weighting, or the default
+    /// pubkey if it never happened
+    pub emergency_triggered_by: Pubkey,
+
+    /// Slot in which the config was last forced into static weighting
+    pub emergency_slot: PodU64,
+
+    /// Slot of the last change to the writable data
+    pub last_write_slot: PodU64,
+
+    /// Number of slots that must pass after a change to the writable data
+    /// before the next one, or zero for no limit
+    pub min_slots_between_writes: PodU64,
+
+    /// [`BoundsData`] account every config written to the record must fall
+    /// within, or the default pubkey if configs are not bounded
+    pub bounds: Pubkey,
+
+    /// Zeroed room for fields added to this version of the header
+    pub reserved: [u8; RecordData::RESERVED_LEN],
+}
+
+impl RecordData {
+    /// Version to fill in on new created accounts
+    ///
+    /// Each version has a single header layout: version 1 is
+    /// [`RecordDataV1`] and version 2 is this struct. New fields of version 2
+    /// take their room from `reserved`, any other change to the layout needs
+    /// a new version.
+    pub const CURRENT_VERSION: u8 = 2;
+
+    /// Length of the room reserved for new header fields
+    pub const RESERVED_LEN: usize = 128;
+
+    /// Version of an account that has never been initialized
+    pub const UNINITIALIZED_VERSION: u8 = 0;
+
+    /// Start of writable account data, after the header fields
+    pub const WRITABLE_START_INDEX: usize = core::mem::size_of::<Self>();
+
+    /// Maximum number of signers in a signer set
+    pub const MAX_SIGNERS: usize = 11;
+
+    /// Maximum number of writer keys
+    pub const MAX_WRITERS: usize = 5;
+
+    /// Start of the history entries, after the weighting config state
+    pub const HISTORY_START_INDEX: usize =
+        Self::WRITABLE_START_INDEX + core::mem::size_of::<WeightingConfigState>();
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+
+    /// Check if the record is frozen
+    pub fn is_frozen(&self) -> bool {
+        self.frozen != 0
+    }
+
+    /// Get the schema of the record data, if it is known to the program
+    pub fn schema(&self) -> Option<RecordSchema> {
+        RecordSchema::from_u8(self.schema)
+    }
+
+    /// Get the proposed new authority, if a transfer is in progress
+    pub fn pending_authority(&self) -> Option<&Pubkey> {
+        (self.pending_authority != Pubkey::default()).then_some(&self.pending_authority)
+    }
+
+    /// Check if changes are approved by a signer set rather than `authority`
+    pub fn is_multisig(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// Get the signers in the signer set
+    pub fn signers(&self) -> &[Pubkey] {
+        let num_signers = usize::from(self.num_signers).min(Self::MAX_SIGNERS);
+        &self.signers[..num_signers]
+    }
+
+    /// Get the keys allowed to write the config without being the admin
+    pub fn writers(&self) -> &[Pubkey] {
+        let num_writers = usize::from(self.num_writers).min(Self::MAX_WRITERS);
+        &self.writers[..num_writers]
+    }
+
+    /// Get the key allowed to force static weighting, if there is one
+    pub fn emergency_authority(&self) -> Option<&Pubkey> {
+        (self.emergency_authority != Pubkey::default()).then_some(&self.emergency_authority)
+    }
+
+    /// Get the key that last forced static weighting and the slot it did so
+    /// in, if it ever happened
+    pub fn emergency_trigger(&self) -> Option<(&Pubkey, Slot)> {
+        (self.emergency_triggered_by != Pubkey::default())
+            .then(|| (&self.emergency_triggered_by, u64::from(self.emergency_slot)))
+    }
+
+    /// Get the bounds account configs must fall within, if there is one
+    pub fn bounds(&self) -> Option<&Pubkey> {
+        (self.bounds != Pubkey::default()).then_some(&self.bounds)
+    }
+
+    /// Get the first slot in which the writable data may change again
+    pub fn next_write_slot(&self) -> Slot {
+        u64::from(self.last_write_slot).saturating_add(self.min_slots_between_writes.into())
+    }
+
+    /// Get the keys that approve changes, the signer set or the authority
+    pub fn approvers(&self) -> &[Pubkey] {
+        if self.is_multisig() {
+            self.signers()
+        } else {
+            core::slice::from_ref(&self.authority)
+        }
+    }
+
+    /// Get the number of approvers needed to approve a change
+    pub fn required_approvals(&self) -> usize {
+        if self.is_multisig() {
+            usize::from(self.threshold)
+        } else {
+            1
+        }
+    }
+
+    /// Check if changes to the weighting config are recorded
+    pub fn has_history(&self) -> bool {
+        self.history_capacity > 0
+    }
+
+    /// Get the number of entries currently held in the history
+    pub fn history_len(&self) -> usize {
+        u64::from(self.history_count).min(u64::from(self.history_capacity)) as usize
+    }
+
+    /// Get the index of the history entry the next change is recorded in
+    pub fn history_next_index(&self) -> usize {
+        u64::from(self.history_count)
+            .checked_rem(u64::from(self.history_capacity))
+            .unwrap_or(0) as usize
+    }
+
+    /// Get the index of the history entry recorded `versions_back` changes
+    /// before the latest one, if the history still holds it
+    pub fn history_index(&self, versions_back: usize) -> Option<usize> {
+        if versions_back >= self.history_len() {
+            return None;
+        }
+        let capacity = usize::from(self.history_capacity);
+        Some((self.history_next_index() + capacity - 1 - versions_back) % capacity)
+    }
+
+    /// Get the end of the history entries, where the record account data
+    /// must extend to
+    pub fn history_end_index(&self) -> usize {
+        Self::history_end_index_for(self.history_capacity)
+    }
+
+    /// Get the end of the history entries for a history holding `capacity`
+    /// entries
+    pub fn history_end_index_for(capacity: u8) -> usize {
+        Self::HISTORY_START_INDEX + usize::from(capacity) * core::mem::size_of::<HistoryEntry>()
+    }
+
+    /// Get the mode of the identity list, if it is known to the program
+    pub fn list_mode(&self) -> Option<ListMode> {
+        ListMode::from_u8(self.list_mode)
+    }
+
+    /// Get the number of identities in the identity list
+    pub fn list_len(&self) -> usize {
+        u64::from(self.list_len) as usize
+    }
+
+    /// Get the start of the identity list, after the history entries
+    pub fn list_start_index(&self) -> usize {
+        self.history_end_index()
+    }
+
+    /// Get the end of the identity list, where the record account data must
+    /// extend to
+    pub fn list_end_index(&self) -> usize {
+        self.list_start_index().saturating_add(
+            self.list_len()
+                .saturating_mul(core::mem::size_of::<Pubkey>()),
+        )
+    }
+
+    /// Get the length of the data following the header that writes may
+    /// change, out of `payload_len` bytes, only the weighting config state
+    /// if anything follows it
+    pub fn writable_len(&self, payload_len: usize) -> usize {
+        if self.has_history() || self.list_len() > 0 {
+            core::mem::size_of::<WeightingConfigState>().min(payload_len)
+        } else {
+            payload_len
+        }
+    }
+
+    /// Check if the data is a finished config rather than one in the middle
+    /// of a change spread over several transactions
+    pub fn is_complete(&self) -> bool {
+        self.complete != 0
+    }
+
+    /// Compute the content hash of the data following the header
+    pub fn compute_content_hash(payload: &[u8]) -> [u8; 32] {
+        solana_sha256_hasher::hash(payload).to_bytes()
+    }
+
+    /// Decode the header of an initialized record account only if its data
+    /// is complete and matches the content hash, so that readers never act
+    /// on a torn or partially written config
+    pub fn unpack_verified(account_data: &[u8]) -> Option<&Self> {
+        let header = Self::unpack(account_data).filter(|header| header.is_initialized())?;
+        let payload = &account_data[Self::WRITABLE_START_INDEX..];
+        (header.is_complete() && header.content_hash == Self::compute_content_hash(payload))
+            .then_some(header)
+    }
+
+    /// Decode the header at the start of a record account's data
+    pub fn unpack(account_data: &[u8]) -> Option<&Self> {
+        account_data
+            .get(..Self::WRITABLE_START_INDEX)
+            .and_then(|bytes| bytemuck::try_from_bytes(bytes).ok())
+    }
+
+    /// Decode the sorted identity list of an initialized record account with
+    /// the [`RecordSchema::WeightingConfig`] schema
+    pub fn unpack_identity_list(account_data: &[u8]) -> Option<&[Pubkey]> {
+        let header = Self::unpack(account_data)?;
+        if !header.is_initialized() || header.schema() != Some(RecordSchema::WeightingConfig) {
+            return None;
+        }
+        account_data
+            .get(header.list_start_index()..header.list_end_index())
+            .and_then(|bytes| bytemuck::try_cast_slice(bytes).ok())
+    }
+}
+
+/// Header of records created before [`RecordData::CURRENT_VERSION`], holding
+/// only the version and the authority
+///
+/// Such records keep accepting `Write`, `SetAuthority` and `CloseAccount`
+/// until they are upgraded in place with `Migrate`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct RecordDataV1 {
+    /// Struct version, [`RecordDataV1::VERSION`]
+    pub version: u8,
+
+    /// The account allowed to update the data
+    pub authority: Pubkey,
+}
+
+impl RecordDataV1 {
+    /// Version of records with this header
+    pub const VERSION: u8 = 1;
+
+    /// Start of writable account data, after the header fields
+    pub const WRITABLE_START_INDEX: usize = core::mem::size_of::<Self>();
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    /// Length of the data of records at this version holding a weighting
+    /// config, the size of the config struct of their clients
+    pub const LEGACY_CONFIG_LEN: usize = 32;
+
+    /// Decode the header of a record account still at this version
+    pub fn unpack(account_data: &[u8]) -> Option<&Self> {
+        account_data
+            .get(..Self::WRITABLE_START_INDEX)
+            .and_then(|bytes| bytemuck::try_from_bytes::<Self>(bytes).ok())
+            .filter(|header| header.is_initialized())
+    }
+
+    /// Decode the weighting config of a record account still at this
+    /// version, written by its clients as the weighting mode and the little
+    /// endian time constant at the start of
+    /// [`RecordDataV1::LEGACY_CONFIG_LEN`] bytes of data left zero otherwise
+    pub fn unpack_legacy_config(account_data: &[u8]) -> Option<WeightingConfig> {
+        Self::unpack(account_data)?;
+        let data = &account_data[Self::WRITABLE_START_INDEX..];
+        if data.len() != Self::LEGACY_CONFIG_LEN {
+            return None;
+        }
+        let (&weighting_mode, rest) = data.split_first()?;
+        let (tc_ms, rest) = rest.split_at(core::mem::size_of::<u64>());
+        if rest.iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        let config = WeightingConfig {
+            weighting_mode,
+            tc_ms: PodU64(tc_ms.try_into().ok()?),
+            reserved: [0; 16],
+        };
+        config.weighting_mode().map(|_| config)
+    }
+}
+
+/// Type of a program account that is not a record, stored in its first byte
+///
+/// Records start with their version instead, which stays below these values.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum AccountType {
+    /// Never initialized
+    Uninitialized = 0,
+    /// A [`ProposalData`] account
+    Proposal = 0x80,
+    /// An [`OverrideData`] account
+    Override = 0x81,
+    /// A [`MultiplierTableData`] account
+    MultiplierTable = 0x82,
+    /// A [`StagingData`] account
+    Staging = 0x83,
+    /// A [`BoundsData`] account
+    Bounds = 0x84,
+}
+
+/// How the identity list of a record applies to dynamic gossip weighting
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum ListMode {
+    /// Listed validators are excluded from dynamic weighting
+    Deny = 0,
+    /// Only listed validators use dynamic weighting
+    Allow = 1,
+}
+
+/// Layout of the data stored after the record header
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum RecordSchema {
+    /// Opaque bytes, only written through `RecordInstruction::Write`
+    Raw = 0,
+    /// A [`WeightingConfigState`] at the start of the writable data
+    WeightingConfig = 1,
+}
+
+/// Gossip weighting mode
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum WeightingMode {
+    /// Static weights
+    Static = 0,
+    /// Dynamic weights, smoothed with an IIR filter
+    Dynamic = 1,
+}
+
+/// Gossip weighting configuration
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct WeightingConfig {
+    /// Weighting mode, see [`WeightingMode`]
+    pub weighting_mode: u8,
+
+    /// IIR time constant in milliseconds
+    pub tc_ms: PodU64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 16],
+}
+
+impl WeightingConfig {
+    /// Smallest accepted IIR time constant in milliseconds
+    pub const MIN_TC_MS: u64 = 1;
+
+    /// Largest accepted IIR time constant in milliseconds, one hour
+    pub const MAX_TC_MS: u64 = 3_600_000;
+
+    /// Create a new config
+    pub fn new(weighting_mode: WeightingMode, tc_ms: u64) -> Self {
+        Self {
+            weighting_mode: weighting_mode as u8,
+            tc_ms: tc_ms.into(),
+            reserved: [0; 16],
+        }
+    }
+
+    /// Get the weighting mode, if it is known to the program
+    pub fn weighting_mode(&self) -> Option<WeightingMode> {
+        WeightingMode::from_u8(self.weighting_mode)
+    }
+}
+
+/// Gossip weighting configuration state, stored at the start of the writable
+/// data of a record with the [`RecordSchema::WeightingConfig`] schema
+///
+/// The active config comes first so that readers unaware of scheduling still
+/// find a config at the start of the writable data, but they should resolve
+/// the config in effect with [`WeightingConfigState::effective_config`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct WeightingConfigState {
+    /// Config in effect until the pending config activates
+    pub active: WeightingConfig,
+
+    /// Config taking effect at `activation_epoch`
+    pub pending: WeightingConfig,
+
+    /// First epoch in which `pending` is in effect, or
+    /// [`WeightingConfigState::NO_PENDING_EPOCH`] if nothing is scheduled
+    pub activation_epoch: PodU64,
+}
+
+impl WeightingConfigState {
+    /// Activation epoch marking that no change is scheduled
+    pub const NO_PENDING_EPOCH: Epoch = 0;
+
+    /// Create a new state with the given config in effect and nothing
+    /// scheduled
+    pub fn new(active: WeightingConfig) -> Self {
+        Self {
+            active,
+            ..Self::default()
+        }
+    }
+
+    /// Get the epoch at which the pending config activates, if any
+    pub fn activation_epoch(&self) -> Option<Epoch> {
+        match u64::from(self.activation_epoch) {
+            Self::NO_PENDING_EPOCH => None,
+            epoch => Some(epoch),
+        }
+    }
+
+    /// Get the config in effect during the given epoch
+    pub fn config_at_epoch(&self, epoch: Epoch) -> &WeightingConfig {
+        match self.activation_epoch() {
+            Some(activation_epoch) if epoch >= activation_epoch => &self.pending,
+            _ => &self.active,
+        }
+    }
+
+    /// Get the config in effect at the given clock
+    pub fn effective_config(&self, clock: &Clock) -> &WeightingConfig {
+        self.config_at_epoch(clock.epoch)
+    }
+
+    /// Make the pending config active if it is in effect during the given
+    /// epoch
+    pub fn activate_pending(&mut self, epoch: Epoch) {
+        if self
+            .activation_epoch()
+            .is_some_and(|activation_epoch| epoch >= activation_epoch)
+        {
+            self.active = self.pending;
+            self.clear_pending();
+        }
+    }
+
+    /// Drop any scheduled change
+    pub fn clear_pending(&mut self) {
+        self.pending = WeightingConfig::default();
+        self.activation_epoch = Self::NO_PENDING_EPOCH.into();
+    }
+
+    /// Decode the state of an initialized record account with the
+    /// [`RecordSchema::WeightingConfig`] schema
+    pub fn unpack_from_record(account_data: &[u8]) -> Option<&Self> {
+        let header = RecordData::unpack(account_data)?;
+        if !header.is_initialized() || header.schema() != Some(RecordSchema::WeightingConfig) {
+            return None;
+        }
+        account_data
+            .get(RecordData::WRITABLE_START_INDEX..)
+            .and_then(|payload| payload.get(..core::mem::size_of::<Self>()))
+            .and_then(|bytes| bytemuck::try_from_bytes(bytes).ok())
+    }
+}
+
+/// Fields of a weighting config that replace those of another config
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct WeightingConfigOverride {
+    /// Bit set of the overridden fields, see
+    /// [`WeightingConfigOverride::WEIGHTING_MODE`] and
+    /// [`WeightingConfigOverride::TC_MS`]
+    pub fields: u8,
+
+    /// Values of the overridden fields, other fields are ignored
+    pub config: WeightingConfig,
+}
+
+impl WeightingConfigOverride {
+    /// Field bit overriding `weighting_mode`
+    pub const WEIGHTING_MODE: u8 = 1 << 0;
+
+    /// Field bit overriding `tc_ms`
+    pub const TC_MS: u8 = 1 << 1;
+
+    /// All known field bits
+    pub const ALL_FIELDS: u8 = Self::WEIGHTING_MODE | Self::TC_MS;
+
+    /// Check if the given field bit is overridden
+    pub fn overrides(&self, field: u8) -> bool {
+        self.fields & field != 0
+    }
+
+    /// Get the given config with the overridden fields replaced
+    pub fn apply(&self, config: &WeightingConfig) -> WeightingConfig {
+        let mut config = *config;
+        if self.overrides(Self::WEIGHTING_MODE) {
+            config.weighting_mode = self.config.weighting_mode;
+        }
+        if self.overrides(Self::TC_MS) {
+            config.tc_ms = self.config.tc_ms;
+        }
+        config
+    }
+}
+
+/// Override of the weighting config of a record for a single validator
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct OverrideData {
+    /// Account type, [`AccountType::Override`] once initialized
+    pub account_type: u8,
+
+    /// Record account holding the overridden config
+    pub config: Pubkey,
+
+    /// Identity of the validator the override applies to
+    pub identity: Pubkey,
+
+    /// Fields replacing those of the record's config
+    pub overrides: WeightingConfigOverride,
+}
+
+impl OverrideData {
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Override as u8
+    }
+
+    /// Decode an initialized override account
+    pub fn unpack(account_data: &[u8]) -> Option<&Self> {
+        bytemuck::try_from_bytes::<Self>(account_data)
+            .ok()
+            .filter(|override_data| override_data.is_initialized())
+    }
+}
+
+/// Resolve the weighting config in effect for a validator at the given clock,
+/// from the record account at `config_address` and the validator's override
+/// account, if it has one
+///
+/// Returns `None` unless the record data is complete and matches its content
+/// hash, see [`RecordData::unpack_verified`].
+pub fn resolve_config(
+    config_address: &Pubkey,
+    config_account_data: &[u8],
+    identity: &Pubkey,
+    override_account_data: Option<&[u8]>,
+    clock: &Clock,
+) -> Option<WeightingConfig> {
+    RecordData::unpack_verified(config_account_data)?;
+    let config =
+        *WeightingConfigState::unpack_from_record(config_account_data)?.effective_config(clock);
+    Some(match override_account_data.and_then(OverrideData::unpack) {
+        Some(override_data)
+            if override_data.config == *config_address && override_data.identity == *identity =>
+        {
+            override_data.overrides.apply(&config)
+        }
+        _ => config,
+    })
+}
+
+/// Multiplier applied to the stake of a single validator in dynamic weighting
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct MultiplierEntry {
+    /// Identity of the validator
+    pub identity: Pubkey,
+
+    /// Multiplier in basis points, [`MultiplierEntry::UNIT_BPS`] leaves the
+    /// stake unchanged
+    pub multiplier_bps: PodU32,
+}
+
+impl MultiplierEntry {
+    /// Multiplier of one in basis points
+    pub const UNIT_BPS: u32 = 10_000;
+
+    /// Create a new entry
+    pub fn new(identity: Pubkey, multiplier_bps: u32) -> Self {
+        Self {
+            identity,
+            multiplier_bps: multiplier_bps.into(),
+        }
+    }
+
+    /// Get the given stake scaled by the multiplier, saturating at
+    /// `u64::MAX`
+    pub fn apply(&self, stake: u64) -> u64 {
+        let scaled = u128::from(stake) * u128::from(u32::from(self.multiplier_bps))
+            / u128::from(Self::UNIT_BPS);
+        u64::try_from(scaled).unwrap_or(u64::MAX)
+    }
+}
+
+/// Header of a table of stake multipliers for the validators weighted by a
+/// record's config, followed by its entries sorted by identity
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct MultiplierTableData {
+    /// Account type, [`AccountType::MultiplierTable`] once initialized
+    pub account_type: u8,
+
+    /// Record account holding the config the multipliers apply to
+    pub config: Pubkey,
+
+    /// Smallest multiplier accepted in the table, in basis points
+    pub min_multiplier_bps: PodU32,
+
+    /// Largest multiplier accepted in the table, in basis points
+    pub max_multiplier_bps: PodU32,
+
+    /// Number of [`MultiplierEntry`] entries following the header
+    pub num_entries: PodU32,
+}
+
+impl MultiplierTableData {
+    /// Start of the entries, after the header fields
+    pub const ENTRIES_START_INDEX: usize = core::mem::size_of::<Self>();
+
+    /// Maximum number of entries in a table
+    pub const MAX_ENTRIES: usize = 256;
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::MultiplierTable as u8
+    }
+
+    /// Check if a multiplier is within the range accepted in the table
+    pub fn accepts(&self, multiplier_bps: u32) -> bool {
+        (u32::from(self.min_multiplier_bps)..=u32::from(self.max_multiplier_bps))
+            .contains(&multiplier_bps)
+    }
+
+    /// Get the end of the entries for a table holding `num_entries` entries
+    pub fn entries_end_index_for(num_entries: usize) -> usize {
+        Self::ENTRIES_START_INDEX
+            .saturating_add(num_entries.saturating_mul(core::mem::size_of::<MultiplierEntry>()))
+    }
+
+    /// Decode an initialized multiplier table account into its header and
+    /// its entries, sorted by identity
+    pub fn unpack(account_data: &[u8]) -> Option<(&Self, &[MultiplierEntry])> {
+        let header = account_data
+            .get(..Self::ENTRIES_START_INDEX)
+            .and_then(|bytes| bytemuck::try_from_bytes::<Self>(bytes).ok())
+            .filter(|header| header.is_initialized())?;
+        let end = Self::entries_end_index_for(u32::from(header.num_entries) as usize);
+        let entries = account_data
+            .get(Self::ENTRIES_START_INDEX..end)
+            .and_then(|bytes| bytemuck::try_cast_slice(bytes).ok())?;
+        Some((header, entries))
+    }
+
+    /// Look up the multiplier of a validator in a multiplier table account,
+    /// by a binary search of its entries
+    pub fn multiplier_bps(account_data: &[u8], identity: &Pubkey) -> Option<u32> {
+        let (_, entries) = Self::unpack(account_data)?;
+        entries
+            .binary_search_by(|entry| entry.identity.cmp(identity))
+            .ok()
+            .map(|index| u32::from(entries[index].multiplier_bps))
+    }
+}
+
+/// Header of the account staging a change to the writable data of a record,
+/// followed by the staged data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct StagingData {
+    /// Account type, [`AccountType::Staging`] once initialized
+    pub account_type: u8,
+
+    /// Record account the staged data is written to
+    pub record: Pubkey,
+
+    /// Sequence number of the record when staging began, the staged data
+    /// is only written if the record has not changed since
+    pub base_sequence: PodU64,
+}
+
+impl StagingData {
+    /// Start of the staged data, after the header fields
+    pub const DATA_START_INDEX: usize = core::mem::size_of::<Self>();
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Staging as u8
+    }
+
+    /// Decode an initialized staging account into its header and the staged
+    /// data
+    pub fn unpack(account_data: &[u8]) -> Option<(&Self, &[u8])> {
+        let header = account_data
+            .get(..Self::DATA_START_INDEX)
+            .and_then(|bytes| bytemuck::try_from_bytes::<Self>(bytes).ok())
+            .filter(|header| header.is_initialized())?;
+        Some((header, &account_data[Self::DATA_START_INDEX..]))
+    }
+}
+
+/// Range of weighting configs accepted in the records referencing it, kept
+/// by a governance authority separate from the records' authorities
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct BoundsData {
+    /// Account type, [`AccountType::Bounds`] once initialized
+    pub account_type: u8,
+
+    /// The account allowed to update the bounds
+    pub authority: Pubkey,
+
+    /// Smallest accepted IIR time constant in milliseconds
+    pub min_tc_ms: PodU64,
+
+    /// Largest accepted IIR time constant in milliseconds
+    pub max_tc_ms: PodU64,
+
+    /// Accepted weighting modes, bit `1 << mode` set for each
+    /// [`WeightingMode`]
+    pub allowed_modes: u8,
+}
+
+impl BoundsData {
+    /// Bits of `allowed_modes` standing for a known weighting mode
+    pub const ALL_MODES: u8 = 1 << WeightingMode::Static as u8 | 1 << WeightingMode::Dynamic as u8;
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Bounds as u8
+    }
+
+    /// Check if a weighting mode is accepted
+    pub fn allows_mode(&self, weighting_mode: u8) -> bool {
+        1u8.checked_shl(u32::from(weighting_mode))
+            .is_some_and(|bit| self.allowed_modes & bit != 0)
+    }
+
+    /// Check if a config falls within the bounds
+    pub fn accepts(&self, config: &WeightingConfig) -> bool {
+        self.allows_mode(config.weighting_mode)
+            && (u64::from(self.min_tc_ms)..=u64::from(self.max_tc_ms))
+                .contains(&u64::from(config.tc_ms))
+    }
+
+    /// Decode an initialized bounds account
+    pub fn unpack(account_data: &[u8]) -> Option<&Self> {
+        bytemuck::try_from_bytes::<Self>(account_data)
+            .ok()
+            .filter(|bounds| bounds.is_initialized())
+    }
+}
+
+/// Change to the weighting config of a record, kept in the record's history
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct HistoryEntry {
+    /// Slot in which the change was made
+    pub slot: PodU64,
+
+    /// Account that approved the change, the authority account or the
+    /// proposer of an executed proposal
+    pub signer: Pubkey,
+
+    /// Weighting config state after the change
+    pub state: WeightingConfigState,
+}
+
+impl HistoryEntry {
+    /// Decode the history of an initialized record account with the
+    /// [`RecordSchema::WeightingConfig`] schema, oldest change first
+    pub fn unpack_history(
+        account_data: &[u8],
+    ) -> Option<impl DoubleEndedIterator<Item = &HistoryEntry>> {
+        let header = RecordData::unpack(account_data)?;
+        if !header.is_initialized() || header.schema() != Some(RecordSchema::WeightingConfig) {
+            return None;
+        }
+        let entries: &[Self] = account_data
+            .get(RecordData::HISTORY_START_INDEX..header.history_end_index())
+            .and_then(|bytes| bytemuck::try_cast_slice(bytes).ok())?;
+
+        // once the history wraps around, the oldest entry is the one
+        // overwritten next
+        let (older, newer) = if header.history_len() < entries.len() {
+            (&entries[..header.history_len()], &entries[..0])
+        } else {
+            let (newer, older) = entries.split_at(header.history_next_index());
+            (older, newer)
+        };
+        Some(older.iter().chain(newer))
+    }
+}
+
+/// Header of a proposal to write bytes to a record once enough of the
+/// record's approvers approve, followed by the bytes to write
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ProposalData {
+    /// Account type, [`AccountType::Proposal`] once initialized
+    pub account_type: u8,
+
+    /// Record account the proposal writes to
+    pub record: Pubkey,
+
+    /// Account that created the proposal and may cancel it
+    pub proposer: Pubkey,
+
+    /// First slot in which the proposal can no longer be approved or executed
+    pub expiry_slot: PodU64,
+
+    /// Offset into the record's writable data to write at
+    pub offset: PodU64,
+
+    /// Approvers that approved the proposal, only the first `num_approvals`
+    /// entries are used
+    pub approvals: [Pubkey; RecordData::MAX_SIGNERS],
+
+    /// Number of entries in use in `approvals`
+    pub num_approvals: u8,
+
+    /// Whether the proposal has been executed
+    pub executed: u8,
+}
+
+impl ProposalData {
+    /// Start of the bytes to write, after the header fields
+    pub const DATA_START_INDEX: usize = core::mem::size_of::<Self>();
+
+    /// Is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Proposal as u8
+    }
+
+    /// Get the approvers that approved the proposal
+    pub fn approvals(&self) -> &[Pubkey] {
+        let num_approvals = usize::from(self.num_approvals).min(RecordData::MAX_SIGNERS);
+        &self.approvals[..num_approvals]
+    }
+
+    /// Count the approvals from keys that still approve changes to the record
+    pub fn num_valid_approvals(&self, record_data: &RecordData) -> usize {
+        let approvers = record_data.approvers();
+        self.approvals()
+            .iter()
+            .filter(|approval| approvers.contains(approval))
+            .count()
+    }
+
+    /// Check if the proposal can no longer be approved or executed
+    pub fn is_expired(&self, slot: Slot) -> bool {
+        slot >= u64::from(self.expiry_slot)
+    }
+
+    /// Decode the header and the bytes to write of an initialized proposal
+    /// account
+    pub fn unpack(account_data: &[u8]) -> Option<(&Self, &[u8])> {
+        if account_data.len() < Self::DATA_START_INDEX {
+            return None;
+        }
+        let (header, data) = account_data.split_at(Self::DATA_START_INDEX);
+        let proposal = bytemuck::try_from_bytes::<Self>(header).ok()?;
+        proposal.is_initialized().then_some((proposal, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Version for tests
+    pub const TEST_VERSION: u8 = 2;
+    /// Pubkey for tests
+    pub const TEST_PUBKEY: Pubkey = Pubkey::new_from_array([100; 32]);
+    /// Bytes for tests
+    pub const TEST_BYTES: [u8; 8] = [42; 8];
+    /// `RecordData` for tests
+    pub const TEST_RECORD_DATA: RecordData = RecordData {
+        version: TEST_VERSION,
+        authority: TEST_PUBKEY,
+        schema: RecordSchema::Raw as u8,
+        pending_authority: Pubkey::new_from_array([0; 32]),
+        signers: [Pubkey::new_from_array([0; 32]); RecordData::MAX_SIGNERS],
+        num_signers: 0,
+        threshold: 0,
+        history_capacity: 0,
+        history_count: PodU64([0; 8]),
+        sequence: PodU64([0; 8]),
+        frozen: 0,
+        list_mode: ListMode::Deny as u8,
+        list_len: PodU64([0; 8]),
+        complete: 1,
+        content_hash: [0; 32],
+        writers: [Pubkey::new_from_array([0; 32]); RecordData::MAX_WRITERS],
+        num_writers: 0,
+        emergency_authority: Pubkey::new_from_array([0; 32]),
+        emergency_triggered_by: Pubkey::new_from_array([0; 32]),
+        emergency_slot: PodU64([0; 8]),
+        last_write_slot: PodU64([0; 8]),
+        min_slots_between_writes: PodU64([0; 8]),
+        bounds: Pubkey::new_from_array([0; 32]),
+        reserved: [0; RecordData::RESERVED_LEN],
+    };
+    /// Time constant for tests
+    pub const TEST_TC_MS: u64 = 30_000;
+
+    #[test]
+    fn serialize_data() {
+        let mut expected = vec![TEST_VERSION];
+        expected.extend_from_slice(&TEST_PUBKEY.to_bytes());
+        expected.push(RecordSchema::Raw as u8);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[0; 32 * RecordData::MAX_SIGNERS]);
+        expected.extend_from_slice(&[0, 0, 0]);
+        expected.extend_from_slice(&[0; 16]);
+        expected.push(0);
+        expected.push(ListMode::Deny as u8);
+        expected.extend_from_slice(&[0; 8]);
+        expected.push(1);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[0; 32 * RecordData::MAX_WRITERS]);
+        expected.push(0);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[0; 8]);
+        expected.extend_from_slice(&[0; 16]);
+        expected.extend_from_slice(&[0; 32]);
+        expected.extend_from_slice(&[0; RecordData::RESERVED_LEN]);
+        assert_eq!(bytemuck::bytes_of(&TEST_RECORD_DATA), expected);
+        assert_eq!(
+            *bytemuck::try_from_bytes::<RecordData>(&expected).unwrap(),
+            TEST_RECORD_DATA,
+        );
+        assert_eq!(expected.len(), RecordData::WRITABLE_START_INDEX);
+        // the layout of version 2 is fixed
+        assert_eq!(RecordData::WRITABLE_START_INDEX, 889);
+    }
+
+    #[test]
+    fn deserialize_invalid_slice() {
+        let mut expected = vec![TEST_VERSION];
+        expected.extend_from_slice(&TEST_PUBKEY.to_bytes());
+        expected.extend_from_slice(&TEST_BYTES);
+        assert!(bytemuck::try_from_bytes::<RecordData>(&expected).is_err());
+    }
+
+    #[test]
+    fn unpack_v1_header() {
+        let mut account_data = vec![RecordDataV1::VERSION];
+        account_data.extend_from_slice(&TEST_PUBKEY.to_bytes());
+        account_data.extend_from_slice(&TEST_BYTES);
+        assert_eq!(RecordDataV1::WRITABLE_START_INDEX, 33);
+        assert_eq!(
+            RecordDataV1::unpack(&account_data),
+            Some(&RecordDataV1 {
+                version: RecordDataV1::VERSION,
+                authority: TEST_PUBKEY,
+            })
+        );
+
+        // current records are not mistaken for old ones
+        assert!(RecordDataV1::unpack(bytemuck::bytes_of(&TEST_RECORD_DATA)).is_none());
+        assert!(RecordDataV1::unpack(&account_data[..32]).is_none());
+    }
+
+    #[test]
+    fn unpack_legacy_config() {
+        let mut account_data = bytemuck::bytes_of(&RecordDataV1 {
+            version: RecordDataV1::VERSION,
+            authority: TEST_PUBKEY,
+        })
+        .to_vec();
+        account_data.push(WeightingMode::Dynamic as u8);
+        account_data.extend_from_slice(&TEST_TC_MS.to_le_bytes());
+        account_data.resize(
+            RecordDataV1::WRITABLE_START_INDEX + RecordDataV1::LEGACY_CONFIG_LEN,
+            0,
+        );
+        assert_eq!(
+            RecordDataV1::unpack_legacy_config(&account_data),
+            Some(WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS))
+        );
+
+        // other data is left raw
+        let mut unknown_mode = account_data.clone();
+        unknown_mode[RecordDataV1::WRITABLE_START_INDEX] = 2;
+        assert_eq!(RecordDataV1::unpack_legacy_config(&unknown_mode), None);
+        let mut trailing_data = account_data.clone();
+        *trailing_data.last_mut().unwrap() = 1;
+        assert_eq!(RecordDataV1::unpack_legacy_config(&trailing_data), None);
+        account_data.push(0);
+        assert_eq!(RecordDataV1::unpack_legacy_config(&account_data), None);
+    }
+
+    #[test]
+    fn record_signers() {
+        let mut record_data = TEST_RECORD_DATA;
+        assert!(!record_data.is_multisig());
+        assert!(record_data.signers().is_empty());
+
+        record_data.signers[0] = TEST_PUBKEY;
+        record_data.num_signers = 1;
+        record_data.threshold = 1;
+        assert!(record_data.is_multisig());
+        assert_eq!(record_data.signers(), &[TEST_PUBKEY]);
+
+        record_data.num_signers = u8::MAX;
+        assert_eq!(record_data.signers().len(), RecordData::MAX_SIGNERS);
+    }
+
+    #[test]
+    fn serialize_weighting_config() {
+        let config = WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS);
+        let mut expected = vec![WeightingMode::Dynamic as u8];
+        expected.extend_from_slice(&TEST_TC_MS.to_le_bytes());
+        expected.extend_from_slice(&[0; 16]);
+        assert_eq!(bytemuck::bytes_of(&config), expected);
+        let decoded = bytemuck::try_from_bytes::<WeightingConfig>(&expected).unwrap();
+        assert_eq!(decoded.weighting_mode(), Some(WeightingMode::Dynamic));
+        assert_eq!(u64::from(decoded.tc_ms), TEST_TC_MS);
+    }
+
+    #[test]
+    fn unknown_weighting_mode() {
+        let config = WeightingConfig {
+            weighting_mode: 7,
+            ..WeightingConfig::default()
+        };
+        assert_eq!(config.weighting_mode(), None);
+    }
+
+    #[test]
+    fn weighting_config_record_round_trip() {
+        let header = RecordData {
+            schema: RecordSchema::WeightingConfig as u8,
+            ..TEST_RECORD_DATA
+        };
+        let mut state =
+            WeightingConfigState::new(WeightingConfig::new(WeightingMode::Static, TEST_TC_MS));
+        state.pending = WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS);
+        state.activation_epoch = 5.into();
+        let mut account_data = bytemuck::bytes_of(&header).to_vec();
+        account_data.extend_from_slice(bytemuck::bytes_of(&state));
+
+        assert_eq!(RecordData::unpack(&account_data), Some(&header));
+        assert_eq!(
+            WeightingConfigState::unpack_from_record(&account_data),
+            Some(&state)
+        );
+        // readers of the legacy layout find the active mode and time constant
+        // at the same offsets
+        let payload = &account_data[RecordData::WRITABLE_START_INDEX..];
+        assert_eq!(payload[0], WeightingMode::Static as u8);
+        assert_eq!(payload[1..9], TEST_TC_MS.to_le_bytes());
+    }
+
+    #[test]
+    fn weighting_config_record_rejects_raw_or_short_data() {
+        let state =
+            WeightingConfigState::new(WeightingConfig::new(WeightingMode::Static, TEST_TC_MS));
+        let mut account_data = bytemuck::bytes_of(&TEST_RECORD_DATA).to_vec();
+        account_data.extend_from_slice(bytemuck::bytes_of(&state));
+        assert_eq!(
+            WeightingConfigState::unpack_from_record(&account_data),
+            None
+        );
+
+        let header = RecordData {
+            schema: RecordSchema::WeightingConfig as u8,
+            ..TEST_RECORD_DATA
+        };
+        let mut account_data = bytemuck::bytes_of(&header).to_vec();
+        account_data.extend_from_slice(&bytemuck::bytes_of(&state)[..9]);
+        assert_eq!(
+            WeightingConfigState::unpack_from_record(&account_data),
+            None
+        );
+    }
+
+    #[test]
+    fn effective_config() {
+        let active = WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS);
+        let pending = WeightingConfig::new(WeightingMode::Static, TEST_TC_MS);
+        let mut state = WeightingConfigState::new(active);
+        let clock = Clock {
+            epoch: 10,
+            ..Clock::default()
+        };
+        assert_eq!(state.activation_epoch(), None);
+        assert_eq!(state.effective_config(&clock), &active);
+
+        state.pending = pending;
+        state.activation_epoch = 11.into();
+        assert_eq!(state.effective_config(&clock), &active);
+        assert_eq!(state.config_at_epoch(11), &pending);
+
+        state.activate_pending(10);
+        assert_eq!(state.activation_epoch(), Some(11));
+        state.activate_pending(12);
+        assert_eq!(state.active, pending);
+        assert_eq!(state.activation_epoch(), None);
+    }
+
+    #[test]
+    fn history_wraps_around() {
+        let mut header = RecordData {
+            schema: RecordSchema::WeightingConfig as u8,
+            history_capacity: 3,
+            history_count: 2.into(),
+            ..TEST_RECORD_DATA
+        };
+        let entry = |slot: u64| HistoryEntry {
+            slot: slot.into(),
+            ..HistoryEntry::default()
+        };
+        let account_data = |header: &RecordData, entries: &[HistoryEntry]| {
+            let mut account_data = bytemuck::bytes_of(header).to_vec();
+            account_data.extend_from_slice(bytemuck::bytes_of(&WeightingConfigState::default()));
+            account_data.extend_from_slice(bytemuck::cast_slice(entries));
+            account_data
+        };
+        let slots = |account_data: &[u8]| {
+            HistoryEntry::unpack_history(account_data)
+                .unwrap()
+                .map(|entry| u64::from(entry.slot))
+                .collect::<Vec<_>>()
+        };
+
+        let entries = [entry(1), entry(2), HistoryEntry::default()];
+        assert_eq!(slots(&account_data(&header, &entries)), vec![1, 2]);
+        assert_eq!(header.history_next_index(), 2);
+        assert_eq!(header.history_index(0), Some(1));
+        assert_eq!(header.history_index(1), Some(0));
+        assert_eq!(header.history_index(2), None);
+
+        header.history_count = 4.into();
+        let entries = [entry(4), entry(2), entry(3)];
+        assert_eq!(slots(&account_data(&header, &entries)), vec![2, 3, 4]);
+        assert_eq!(header.history_next_index(), 1);
+        assert_eq!(header.history_index(0), Some(0));
+        assert_eq!(header.history_index(2), Some(1));
+        assert_eq!(header.history_index(3), None);
+
+        // the account must hold every entry
+        let account_data = account_data(&header, &entries[..2]);
+        assert!(HistoryEntry::unpack_history(&account_data).is_none());
+    }
+
+    #[test]
+    fn identity_list_follows_history() {
+        let header = RecordData {
+            schema: RecordSchema::WeightingConfig as u8,
+            history_capacity: 1,
+            list_mode: ListMode::Allow as u8,
+            list_len: 2.into(),
+            ..TEST_RECORD_DATA
+        };
+        let identities = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        let mut account_data = bytemuck::bytes_of(&header).to_vec();
+        account_data.extend_from_slice(bytemuck::bytes_of(&WeightingConfigState::default()));
+        account_data.extend_from_slice(bytemuck::bytes_of(&HistoryEntry::default()));
+        account_data.extend_from_slice(bytemuck::cast_slice(&identities));
+        assert_eq!(header.list_mode(), Some(ListMode::Allow));
+        assert_eq!(header.list_end_index(), account_data.len());
+        assert_eq!(
+            RecordData::unpack_identity_list(&account_data),
+            Some(&identities[..])
+        );
+
+        // the account must hold every identity
+        assert!(
+            RecordData::unpack_identity_list(&account_data[..account_data.len() - 1]).is_none()
+        );
+    }
+
+    #[test]
+    fn multiplier_table_lookup() {
+        let header = MultiplierTableData {
+            account_type: AccountType::MultiplierTable as u8,
+            config: TEST_PUBKEY,
+            min_multiplier_bps: 5_000.into(),
+            max_multiplier_bps: 20_000.into(),
+            num_entries: 2.into(),
+        };
+        let entries = [
+            MultiplierEntry::new(Pubkey::new_from_array([1; 32]), 5_000),
+            MultiplierEntry::new(Pubkey::new_from_array([2; 32]), 20_000),
+        ];
+        let mut account_data = bytemuck::bytes_of(&header).to_vec();
+        account_data.extend_from_slice(bytemuck::cast_slice(&entries));
+
+        assert_eq!(
+            MultiplierTableData::unpack(&account_data),
+            Some((&header, &entries[..]))
+        );
+        assert_eq!(
+            MultiplierTableData::multiplier_bps(&account_data, &entries[1].identity),
+            Some(20_000)
+        );
+        assert_eq!(
+            MultiplierTableData::multiplier_bps(&account_data, &TEST_PUBKEY),
+            None
+        );
+        assert!(header.accepts(MultiplierEntry::UNIT_BPS));
+        assert!(!header.accepts(20_001));
+        assert_eq!(entries[0].apply(1_000), 500);
+        assert_eq!(entries[1].apply(u64::MAX), u64::MAX);
+
+        // the account must hold every entry
+        assert!(MultiplierTableData::unpack(&account_data[..account_data.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn bounds_accept_configs() {
+        let bounds = BoundsData {
+            account_type: AccountType::Bounds as u8,
+            authority: TEST_PUBKEY,
+            min_tc_ms: 1_000.into(),
+            max_tc_ms: 60_000.into(),
+            allowed_modes: 1 << WeightingMode::Dynamic as u8,
+        };
+        assert_eq!(
+            BoundsData::unpack(bytemuck::bytes_of(&bounds)),
+            Some(&bounds)
+        );
+        assert!(bounds.accepts(&WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS)));
+        assert!(!bounds.accepts(&WeightingConfig::new(WeightingMode::Static, TEST_TC_MS)));
+        assert!(!bounds.accepts(&WeightingConfig::new(WeightingMode::Dynamic, 999)));
+        assert!(!bounds.accepts(&WeightingConfig::new(WeightingMode::Dynamic, 60_001)));
+        assert!(!bounds.allows_mode(u8::MAX));
+
+        let uninitialized = BoundsData {
+            account_type: AccountType::Uninitialized as u8,
+            ..bounds
+        };
+        assert!(BoundsData::unpack(bytemuck::bytes_of(&uninitialized)).is_none());
+    }
+
+    #[test]
+    fn resolve_config_with_override() {
+        let config = WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS);
+        let state = WeightingConfigState::new(config);
+        let header = RecordData {
+            schema: RecordSchema::WeightingConfig as u8,
+            content_hash: RecordData::compute_content_hash(bytemuck::bytes_of(&state)),
+            ..TEST_RECORD_DATA
+        };
+        let mut config_account_data = bytemuck::bytes_of(&header).to_vec();
+        config_account_data.extend_from_slice(bytemuck::bytes_of(&state));
+        let config_address = Pubkey::new_from_array([1; 32]);
+        let identity = Pubkey::new_from_array([2; 32]);
+        let clock = Clock::default();
+
+        let override_data = OverrideData {
+            account_type: AccountType::Override as u8,
+            config: config_address,
+            identity,
+            overrides: WeightingConfigOverride {
+                fields: WeightingConfigOverride::WEIGHTING_MODE,
+                config: WeightingConfig::new(WeightingMode::Static, 1),
+            },
+        };
+        let override_account_data = bytemuck::bytes_of(&override_data);
+
+        let resolved = resolve_config(
+            &config_address,
+            &config_account_data,
+            &identity,
+            Some(override_account_data),
+            &clock,
+        )
+        .unwrap();
+        assert_eq!(resolved.weighting_mode(), Some(WeightingMode::Static));
+        assert_eq!(u64::from(resolved.tc_ms), TEST_TC_MS);
+
+        // overrides of other validators or configs do not apply
+        for (config_address, identity) in [(config_address, TEST_PUBKEY), (TEST_PUBKEY, identity)] {
+            let resolved = resolve_config(
+                &config_address,
+                &config_account_data,
+                &identity,
+                Some(override_account_data),
+                &clock,
+            );
+            assert_eq!(resolved, Some(config));
+        }
+    }
+
+    #[test]
+    fn unpack_verified_rejects_torn_data() {
+        let payload = TEST_BYTES;
+        let mut header = RecordData {
+            content_hash: RecordData::compute_content_hash(&payload),
+            ..TEST_RECORD_DATA
+        };
+        let account_data = |header: &RecordData, payload: &[u8]| {
+            let mut account_data = bytemuck::bytes_of(header).to_vec();
+            account_data.extend_from_slice(payload);
+            account_data
+        };
+        assert_eq!(
+            RecordData::unpack_verified(&account_data(&header, &payload)),
+            Some(&header)
+        );
+
+        // data changed after the hash was computed
+        assert!(RecordData::unpack_verified(&account_data(&header, &[0; 8])).is_none());
+
+        // a change spread over several transactions is in progress
+        header.complete = 0;
+        assert!(RecordData::unpack_verified(&account_data(&header, &payload)).is_none());
+    }
+
+    #[test]
+    fn proposal_round_trip() {
+        let record_data = RecordData {
+            signers: [TEST_PUBKEY; RecordData::MAX_SIGNERS],
+            num_signers: 1,
+            threshold: 1,
+            ..TEST_RECORD_DATA
+        };
+        let mut proposal = ProposalData {
+            account_type: AccountType::Proposal as u8,
+            record: TEST_PUBKEY,
+            proposer: TEST_PUBKEY,
+            expiry_slot: 100.into(),
+            offset: 0.into(),
+            approvals: [Pubkey::default(); RecordData::MAX_SIGNERS],
+            num_approvals: 0,
+            executed: 0,
+        };
+        proposal.approvals[0] = Pubkey::new_from_array([1; 32]);
+        proposal.approvals[1] = TEST_PUBKEY;
+        proposal.num_approvals = 2;
+        let mut account_data = bytemuck::bytes_of(&proposal).to_vec();
+        account_data.extend_from_slice(&TEST_BYTES);
+
+        let (decoded, data) = ProposalData::unpack(&account_data).unwrap();
+        assert_eq!(decoded, &proposal);
+        assert_eq!(data, &TEST_BYTES);
+        assert_eq!(decoded.num_valid_approvals(&record_data), 1);
+        assert!(!decoded.is_expired(99));
+        assert!(decoded.is_expired(100));
+
+        // a record is never mistaken for a proposal
+        let mut account_data = bytemuck::bytes_of(&record_data).to_vec();
+        account_data.extend_from_slice(&[0; ProposalData::DATA_START_INDEX]);
+        assert_eq!(ProposalData::unpack(&account_data), None);
+    }
+}
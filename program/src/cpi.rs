@@ -0,0 +1,55 @@
+//! Helpers for programs reading records through cross-program invocation
+
+use {
+    crate::{
+        check_id, id, instruction,
+        state::WeightingConfig,
+        syscall::{get_return_data, invoke},
+    },
+    solana_account_info::AccountInfo,
+    solana_msg::msg,
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+};
+
+/// Get the weighting config in effect for a validator by invoking
+/// `GetEffectiveConfig` on the record program
+///
+/// `override_info` is the validator's override account, at the address found
+/// by `find_override_address` for the record and identity, which need not
+/// exist.
+pub fn get_effective_config<'a>(
+    program_info: &AccountInfo<'a>,
+    record_info: &AccountInfo<'a>,
+    override_info: &AccountInfo<'a>,
+    identity: &Pubkey,
+) -> Result<WeightingConfig, ProgramError> {
+    if !check_id(program_info.key) {
+        msg!(
+            "Record program account is {}, expected {}",
+            program_info.key,
+            id()
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    invoke(
+        &instruction::get_effective_config(record_info.key, identity),
+        &[
+            record_info.clone(),
+            override_info.clone(),
+            program_info.clone(),
+        ],
+    )?;
+
+    let (program_id, return_data) = get_return_data().ok_or(ProgramError::InvalidAccountData)?;
+    if !check_id(&program_id) {
+        msg!(
+            "Return data set by {} instead of the record program",
+            program_id
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    bytemuck::try_from_bytes::<WeightingConfig>(&return_data)
+        .copied()
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
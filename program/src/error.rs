@@ -15,6 +15,86 @@ pub enum RecordError {
     /// Calculation overflow
     #[error("Calculation overflow")]
     Overflow,
+
+    /// Weighting mode is not known to the program
+    #[error("Weighting mode is not known to the program")]
+    InvalidWeightingMode,
+
+    /// Time constant is outside of the accepted range
+    #[error("Time constant is outside of the accepted range")]
+    InvalidTimeConstant,
+
+    /// Activation epoch is not in the future
+    #[error("Activation epoch is not in the future")]
+    InvalidActivationEpoch,
+
+    /// No authority transfer is in progress
+    #[error("No authority transfer is in progress")]
+    NoPendingAuthority,
+
+    /// Not enough of the record's signers signed
+    #[error("Not enough of the record's signers signed")]
+    NotEnoughSigners,
+
+    /// Signer set or threshold is invalid
+    #[error("Signer set or threshold is invalid")]
+    InvalidSignerSet,
+
+    /// Proposal expired
+    #[error("Proposal expired")]
+    ProposalExpired,
+
+    /// Proposal already executed
+    #[error("Proposal already executed")]
+    ProposalAlreadyExecuted,
+
+    /// Config version not held in the history
+    #[error("Config version not held in the history")]
+    VersionNotInHistory,
+
+    /// Record changed since the expected sequence number
+    #[error("Record changed since the expected sequence number")]
+    StaleSequence,
+
+    /// Record is frozen
+    #[error("Record is frozen")]
+    AccountFrozen,
+
+    /// Identity is already on the identity list
+    #[error("Identity is already on the identity list")]
+    AlreadyListed,
+
+    /// Identity is not on the identity list
+    #[error("Identity is not on the identity list")]
+    NotListed,
+
+    /// Multiplier is outside of the range accepted in the table
+    #[error("Multiplier is outside of the range accepted in the table")]
+    MultiplierOutOfRange,
+
+    /// Multiplier table has no room for more entries
+    #[error("Multiplier table has no room for more entries")]
+    MultiplierTableFull,
+
+    /// Identity has no entry in the multiplier table
+    #[error("Identity has no entry in the multiplier table")]
+    MultiplierNotFound,
+
+    /// Staged data does not match the expected hash
+    #[error("Staged data does not match the expected hash")]
+    StagedHashMismatch,
+
+    /// Record has no room for more writers
+    #[error("Record has no room for more writers")]
+    TooManyWriters,
+
+    /// Record was written too recently to change again
+    #[error("Record was written too recently to change again")]
+    WriteTooSoon,
+
+    /// Weighting config is outside the record's bounds
+    #[error("Weighting config is outside the record's bounds")]
+    ConfigOutOfBounds,
 }
 impl From<RecordError> for ProgramError {
     fn from(e: RecordError) -> Self {
@@ -1,7 +1,10 @@
 //! Program instructions
 
 use {
-    crate::id,
+    crate::{
+        id,
+        state::{ListMode, MultiplierEntry, WeightingConfig, WeightingConfigOverride},
+    },
     solana_instruction::{AccountMeta, Instruction},
     solana_program_error::ProgramError,
     solana_pubkey::Pubkey,
@@ -9,6 +12,15 @@ use {
 };
 
 /// Instructions supported by the program
+///
+/// Instructions approved by the record authority accept additional signer
+/// accounts after the ones they list. While the record has a signer set,
+/// `threshold` of its signers must sign, as the authority account or as
+/// additional signer accounts, and the record authority itself is ignored.
+///
+/// Instructions writing the config or overrides of a record that references
+/// a bounds account expect it among those additional accounts as well, see
+/// [`with_bounds`].
 #[derive(Clone, Debug, PartialEq)]
 pub enum RecordInstruction<'a> {
     /// Create a new record
@@ -21,10 +33,13 @@ pub enum RecordInstruction<'a> {
 
     /// Write to the provided record account
     ///
+    /// Records still at version 1 are written after their shorter header,
+    /// approved by their authority alone.
+    ///
     /// Accounts expected by this instruction:
     ///
     /// 0. `[writable]` Record account, must be previously initialized
-    /// 1. `[signer]` Current record authority
+    /// 1. `[signer]` Current record authority or a writer
     Write {
         /// Offset to start writing record, expressed as `u64`.
         offset: u64,
@@ -34,6 +49,11 @@ pub enum RecordInstruction<'a> {
 
     /// Update the authority of the provided record account
     ///
+    /// The new authority does not sign, so a mistyped key cannot be recovered
+    /// from. Prefer `ProposeAuthority` followed by `AcceptAuthority`. Any
+    /// transfer in progress is cancelled. Records still at version 1 are
+    /// accepted.
+    ///
     /// Accounts expected by this instruction:
     ///
     /// 0. `[writable]` Record account, must be previously initialized
@@ -42,7 +62,9 @@ pub enum RecordInstruction<'a> {
     SetAuthority,
 
     /// Close the provided record account, draining lamports to recipient
-    /// account
+    /// account. The record data is wiped and the account is handed back to
+    /// the system program, so it cannot be revived later in the transaction.
+    /// Records still at version 1 are accepted.
     ///
     /// Accounts expected by this instruction:
     ///
@@ -51,20 +73,566 @@ pub enum RecordInstruction<'a> {
     /// 2. `[]` Receiver of account lamports
     CloseAccount,
 
-    /// Reallocate additional space in a record account
+    /// Grow or shrink the data of a record account
     ///
-    /// If the record account already has enough space to hold the specified
-    /// data length, then the instruction does nothing.
+    /// The account stays rent exempt: the payer tops up the rent when the
+    /// account grows, and the receiver gets the excess lamports when it
+    /// shrinks. Typed records cannot shrink below their config state and
+    /// history.
     ///
     /// Accounts expected by this instruction:
     ///
     /// 0. `[writable]` The record account to reallocate
     /// 1. `[signer]` The account's owner
+    /// 2. `[writable, signer]` Payer of the additional rent
+    /// 3. `[writable]` Receiver of the excess lamports
+    /// 4. `[]` System program
     Reallocate {
         /// The length of the data to hold in the record account excluding meta
         /// data
         data_length: u64,
     },
+
+    /// Write a validated gossip weighting config to the provided record
+    /// account, taking effect immediately and dropping any scheduled change
+    ///
+    /// The record adopts the `WeightingConfig` schema, after which raw writes
+    /// are only accepted if they leave a valid config in place.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Current record authority or a writer
+    SetWeightingConfig {
+        /// The config to store at the start of the record data
+        config: WeightingConfig,
+    },
+
+    /// Schedule a validated gossip weighting config to take effect at the
+    /// start of a future epoch, replacing any previously scheduled change
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must have the `WeightingConfig` schema
+    /// 1. `[signer]` Current record authority
+    ScheduleWeightingConfig {
+        /// The config to activate
+        config: WeightingConfig,
+        /// First epoch in which the config is in effect
+        activation_epoch: u64,
+    },
+
+    /// Propose a new authority for the provided record account, replacing any
+    /// earlier proposal. The current authority stays in control until the new
+    /// authority accepts.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Current record authority
+    /// 2. `[]` Proposed new record authority
+    ProposeAuthority,
+
+    /// Complete an authority transfer started with `ProposeAuthority`
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Proposed new record authority
+    AcceptAuthority,
+
+    /// Cancel an authority transfer started with `ProposeAuthority`
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Current record authority
+    CancelAuthorityTransfer,
+
+    /// Replace the signer set of the provided record account, approved under
+    /// the current signer set. An empty set with a zero threshold hands control
+    /// back to the record authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Current record authority
+    SetSigners {
+        /// Number of signers required to approve a change
+        threshold: u8,
+        /// New signer set
+        signers: &'a [Pubkey],
+    },
+
+    /// Propose writing to the provided record account once enough of its
+    /// approvers approve, counting the proposer's approval
+    ///
+    /// The proposal account must be owned by the program and sized to hold the
+    /// proposal header followed by exactly the proposed bytes.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Proposal account, must be uninitialized
+    /// 1. `[]` Record account, must be previously initialized
+    /// 2. `[signer]` Proposer, the record authority or one of its signers
+    CreateProposal {
+        /// First slot in which the proposal can no longer be approved or
+        /// executed
+        expiry_slot: u64,
+        /// Offset to start writing record, expressed as `u64`.
+        offset: u64,
+        /// Data to replace the existing record data
+        data: &'a [u8],
+    },
+
+    /// Approve a proposal
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Proposal account
+    /// 1. `[]` Record account the proposal writes to
+    /// 2. `[signer]` Approver, the record authority or one of its signers
+    ApproveProposal,
+
+    /// Write the proposed bytes to the record account once enough of the
+    /// record's current approvers approved the proposal
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Proposal account
+    /// 1. `[writable]` Record account the proposal writes to
+    ExecuteProposal,
+
+    /// Close a proposal account, draining lamports to recipient account and
+    /// wiping it like `CloseAccount`
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Proposal account
+    /// 1. `[signer]` Proposer
+    /// 2. `[]` Receiver of account lamports
+    CancelProposal,
+
+    /// Record changes to the weighting config in a history of the given
+    /// number of entries. The newest recorded changes that fit are kept, and
+    /// the current config state becomes the first entry of a history that
+    /// holds none. A capacity of zero stops recording.
+    ///
+    /// The record must hold a weighting config. Its account grows to the end
+    /// of the history if it does not already extend that far.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    /// 2. `[writable, signer]` Payer of the additional rent
+    /// 3. `[]` System program
+    SetHistoryCapacity {
+        /// Number of changes kept in the history
+        capacity: u8,
+    },
+
+    /// Make the config that was active after an earlier change in the
+    /// history active again, dropping any scheduled change. The rollback is
+    /// recorded in the history like any other change.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    Rollback {
+        /// Number of changes to go back from the latest one, must be at
+        /// least one and less than the number of changes in the history
+        versions_back: u8,
+    },
+
+    /// Write to the provided record account, only if no other change was
+    /// made to it since the given sequence number
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Current record authority or a writer
+    WriteIfSequence {
+        /// Sequence number the record must be at
+        expected_sequence: u64,
+        /// Offset to start writing record, expressed as `u64`.
+        offset: u64,
+        /// Data to replace the existing record data
+        data: &'a [u8],
+    },
+
+    /// Create and initialize a record account at the address derived from
+    /// the authority and a profile name, see `find_config_address`. The
+    /// account has room for a weighting config state.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Config account, must not exist yet
+    /// 1. `[]` Record authority
+    /// 2. `[writable, signer]` Payer of the account rent
+    /// 3. `[]` System program
+    CreateConfig {
+        /// Profile name, at most `MAX_CONFIG_NAME_LEN` bytes
+        name: &'a str,
+    },
+
+    /// Permanently freeze the provided record account. No instruction can
+    /// change or close a frozen record, so its data must be complete.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    Freeze,
+
+    /// Create or update the override of a record's weighting config for a
+    /// single validator, at the address derived from the record and the
+    /// validator identity, see `find_override_address`
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Override account
+    /// 1. `[]` Record account, must hold a weighting config
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable, signer]` Payer of the override account rent
+    /// 4. `[]` System program
+    SetOverride {
+        /// Identity of the validator the override applies to
+        identity: Pubkey,
+        /// Fields replacing those of the record's config
+        overrides: WeightingConfigOverride,
+    },
+
+    /// Remove the override of a record's weighting config for a validator,
+    /// closing the override account like `CloseAccount`
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Override account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable]` Receiver of account lamports
+    ClearOverride,
+
+    /// Insert a validator identity into the sorted identity list of a record
+    /// holding a weighting config, growing the account by the size of a
+    /// pubkey if it does not have room for it
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must hold a weighting config
+    /// 1. `[signer]` Record authority
+    /// 2. `[writable, signer]` Payer of the additional rent
+    /// 3. `[]` System program
+    AddToList {
+        /// Identity of the validator to list
+        identity: Pubkey,
+    },
+
+    /// Remove a validator identity from the identity list of a record,
+    /// shrinking the account if the list is at its end
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must hold a weighting config
+    /// 1. `[signer]` Record authority
+    /// 2. `[writable]` Receiver of the rent no longer needed
+    RemoveFromList {
+        /// Identity of the validator to remove
+        identity: Pubkey,
+    },
+
+    /// Set how the identity list of a record applies to dynamic weighting
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must hold a weighting config
+    /// 1. `[signer]` Record authority
+    SetListMode {
+        /// List mode, see `ListMode`
+        mode: u8,
+    },
+
+    /// Create the table of stake multipliers for a record holding a
+    /// weighting config, at the address derived from the record, see
+    /// `find_multiplier_table_address`. The table starts out empty.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Multiplier table account, must not exist yet
+    /// 1. `[]` Record account, must hold a weighting config
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable, signer]` Payer of the table account rent
+    /// 4. `[]` System program
+    CreateMultiplierTable {
+        /// Smallest multiplier accepted in the table, in basis points
+        min_multiplier_bps: u32,
+        /// Largest multiplier accepted in the table, in basis points
+        max_multiplier_bps: u32,
+    },
+
+    /// Insert entries into a multiplier table, replacing the multipliers of
+    /// validators that already have one, and grow the account to hold them
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Multiplier table account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable, signer]` Payer of the additional rent
+    /// 4. `[]` System program
+    SetMultipliers {
+        /// Entries to insert or update, later entries win for a repeated
+        /// identity
+        entries: &'a [MultiplierEntry],
+    },
+
+    /// Delete the entries of the given validators from a multiplier table,
+    /// and shrink the account accordingly
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Multiplier table account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable]` Receiver of the rent no longer needed
+    RemoveMultipliers {
+        /// Identities of the validators whose entries to delete
+        identities: &'a [Pubkey],
+    },
+
+    /// Mark the data of a record as finished or not. Writers spreading a
+    /// change over several transactions clear the flag in the first one and
+    /// set it in the last one, so that readers verifying the record ignore
+    /// the data in between.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority or a writer
+    SetComplete {
+        /// Whether the data is a finished config
+        complete: bool,
+    },
+
+    /// Start staging a change to the writable data of a record too large for
+    /// one transaction, in an account at the address derived from the
+    /// record, see `find_staging_address`. The staged data starts out as a
+    /// copy of the data `Write` may change.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Staging account, must not exist yet
+    /// 1. `[]` Record account, must be previously initialized
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable, signer]` Payer of the staging account rent
+    /// 4. `[]` System program
+    BeginStagedWrite,
+
+    /// Write to the staged data of a record, leaving the record unchanged
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Staging account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    StageChunk {
+        /// Offset to start writing the staged data, expressed as `u64`.
+        offset: u64,
+        /// Data to replace the existing staged data
+        data: &'a [u8],
+    },
+
+    /// Write the staged data to the record at once, like `Write` at offset
+    /// zero, and close the staging account. Fails if the record changed
+    /// since staging began.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Staging account
+    /// 1. `[writable]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable]` Receiver of the staging account lamports
+    CommitStagedWrite {
+        /// SHA-256 hash the staged data must have
+        expected_hash: [u8; 32],
+    },
+
+    /// Discard the staged data and close the staging account, leaving the
+    /// record unchanged
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Staging account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable]` Receiver of the staging account lamports
+    AbortStagedWrite,
+
+    /// Allow or stop a key to write the config of the provided record. A
+    /// writer may approve `Write`, `WriteIfSequence`, `SetWeightingConfig`
+    /// and `SetComplete` in place of the record authority, which keeps every other change,
+    /// including managing writers and scheduling config changes. Raw writes
+    /// approved by a writer may not change the pending config or its
+    /// activation epoch.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    SetWriter {
+        /// Writer key
+        writer: Pubkey,
+        /// Whether the key may write, false removes it
+        allowed: bool,
+    },
+
+    /// Set the key allowed to force the provided record into static
+    /// weighting with `EmergencyStatic`, the default pubkey removes it.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    SetEmergencyAuthority {
+        /// Emergency authority
+        emergency_authority: Pubkey,
+    },
+
+    /// Force the active config of the provided record into static weighting,
+    /// keeping its time constant and dropping any scheduled config. Approved
+    /// by the emergency authority alone, which can make no other change, and
+    /// recorded in the header along with the current slot.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must hold a weighting config
+    /// 1. `[signer]` Emergency authority
+    EmergencyStatic,
+
+    /// Set the number of slots that must pass after a change to the writable
+    /// data of the provided record before the next one. `EmergencyStatic` is
+    /// never held back.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    SetMinSlotsBetweenWrites {
+        /// Minimum number of slots between changes, zero for no limit
+        min_slots: u64,
+    },
+
+    /// Initialize a bounds account, holding the range of weighting configs
+    /// accepted in the records referencing it.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Bounds account, must be uninitialized
+    /// 1. `[signer]` Governance authority
+    CreateBounds {
+        /// Smallest accepted IIR time constant in milliseconds
+        min_tc_ms: u64,
+        /// Largest accepted IIR time constant in milliseconds
+        max_tc_ms: u64,
+        /// Accepted weighting modes, bit `1 << mode` set for each
+        allowed_modes: u8,
+    },
+
+    /// Replace the range of weighting configs accepted by a bounds account.
+    /// Configs already written are kept until they next change.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Bounds account, must be previously initialized
+    /// 1. `[signer]` Governance authority
+    UpdateBounds {
+        /// Smallest accepted IIR time constant in milliseconds
+        min_tc_ms: u64,
+        /// Largest accepted IIR time constant in milliseconds
+        max_tc_ms: u64,
+        /// Accepted weighting modes, bit `1 << mode` set for each
+        allowed_modes: u8,
+    },
+
+    /// Set the bounds account every config written to the provided record
+    /// must fall within, the default pubkey removes it. `EmergencyStatic`
+    /// is not bounded. The new bounds account is expected among the
+    /// additional accounts and must accept the config in effect.
+    ///
+    /// Removing or replacing the bounds a record already references also
+    /// needs the current bounds account and its governance authority as a
+    /// signer among the additional accounts, see
+    /// [`with_bounds_authority`].
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be previously initialized
+    /// 1. `[signer]` Record authority
+    SetBounds {
+        /// Bounds account
+        bounds: Pubkey,
+    },
+
+    /// Upgrade a record still at version 1 to the current header in place.
+    /// The account is resized by the difference in header size and the data
+    /// moves after the new header, which starts out with the old authority
+    /// and no other settings. Data holding a valid weighting config in the
+    /// layout of version 1 clients, see `RecordDataV1::unpack_legacy_config`,
+    /// becomes the config state of a record with the weighting config schema
+    /// instead.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Record account, must be at version 1
+    /// 1. `[signer]` Record authority
+    /// 2. `[writable, signer]` Payer of the additional rent, receives the
+    ///    rent no longer needed if the account shrinks
+    /// 3. `[]` System program
+    Migrate,
+
+    /// Return the weighting config in effect for a validator through the
+    /// return data, as the bytes of a `WeightingConfig`. Applies any change
+    /// scheduled for the current epoch and the validator's override, and
+    /// fails unless the record holds a complete weighting config. Changes no
+    /// account, for other programs to read the config through CPI, see
+    /// `cpi::get_effective_config`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` Record account, must hold a weighting config
+    /// 1. `[]` Override account of the validator, see
+    ///    `find_override_address`, which need not exist
+    GetEffectiveConfig {
+        /// Identity of the validator
+        identity: Pubkey,
+    },
+
+    /// Replace the range of multipliers accepted in a multiplier table. Every
+    /// entry already in the table must fall within the new range.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Multiplier table account
+    /// 1. `[]` Record account, must hold a weighting config
+    /// 2. `[signer]` Record authority
+    SetMultiplierRange {
+        /// Smallest multiplier accepted in the table, in basis points
+        min_multiplier_bps: u32,
+        /// Largest multiplier accepted in the table, in basis points
+        max_multiplier_bps: u32,
+    },
+
+    /// Close a multiplier table, returning its lamports to a receiver
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` Multiplier table account
+    /// 1. `[]` Record account
+    /// 2. `[signer]` Record authority
+    /// 3. `[writable]` Receiver of account lamports
+    CloseMultiplierTable,
 }
 
 impl<'a> RecordInstruction<'a> {
@@ -107,31 +675,544 @@ impl<'a> RecordInstruction<'a> {
 
                 Self::Reallocate { data_length }
             }
-            _ => return Err(ProgramError::InvalidInstructionData),
-        })
-    }
+            5 => {
+                let config = rest
+                    .get(..size_of::<WeightingConfig>())
+                    .and_then(|slice| bytemuck::try_from_bytes::<WeightingConfig>(slice).ok())
+                    .copied()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
 
-    /// Packs a [`RecordInstruction`] into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match self {
-            Self::Initialize => buf.push(0),
-            Self::Write { offset, data } => {
-                buf.push(1);
-                buf.extend_from_slice(&offset.to_le_bytes());
-                buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
-                buf.extend_from_slice(data);
-            }
-            Self::SetAuthority => buf.push(2),
-            Self::CloseAccount => buf.push(3),
-            Self::Reallocate { data_length } => {
-                buf.push(4);
-                buf.extend_from_slice(&data_length.to_le_bytes());
+                Self::SetWeightingConfig { config }
             }
-        };
-        buf
-    }
-}
+            6 => {
+                let (config, rest) = rest
+                    .split_at_checked(size_of::<WeightingConfig>())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let config = *bytemuck::try_from_bytes::<WeightingConfig>(config)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let activation_epoch = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::ScheduleWeightingConfig {
+                    config,
+                    activation_epoch,
+                }
+            }
+            7 => Self::ProposeAuthority,
+            8 => Self::AcceptAuthority,
+            9 => Self::CancelAuthorityTransfer,
+            10 => {
+                let (&threshold, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (&num_signers, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let signers = rest
+                    .get(..usize::from(num_signers).saturating_mul(size_of::<Pubkey>()))
+                    .and_then(|slice| bytemuck::try_cast_slice(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetSigners { threshold, signers }
+            }
+            11 => {
+                let expiry_slot = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let rest = &rest[U64_BYTES..];
+                let offset = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (length, data) = rest[U64_BYTES..]
+                    .split_at_checked(U32_BYTES)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let length = u32::from_le_bytes(
+                    length
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                ) as usize;
+
+                Self::CreateProposal {
+                    expiry_slot,
+                    offset,
+                    data: data
+                        .get(..length)
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                }
+            }
+            12 => Self::ApproveProposal,
+            13 => Self::ExecuteProposal,
+            14 => Self::CancelProposal,
+            15 => {
+                let &capacity = rest.first().ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetHistoryCapacity { capacity }
+            }
+            16 => {
+                let &versions_back = rest.first().ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::Rollback { versions_back }
+            }
+            17 => {
+                let expected_sequence = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let rest = &rest[U64_BYTES..];
+                let offset = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (length, data) = rest[U64_BYTES..]
+                    .split_at_checked(U32_BYTES)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let length = u32::from_le_bytes(
+                    length
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                ) as usize;
+
+                Self::WriteIfSequence {
+                    expected_sequence,
+                    offset,
+                    data: data
+                        .get(..length)
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                }
+            }
+            18 => {
+                let (&length, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let name = rest
+                    .get(..usize::from(length))
+                    .and_then(|slice| std::str::from_utf8(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::CreateConfig { name }
+            }
+            19 => Self::Freeze,
+            20 => {
+                let (identity, rest) = rest
+                    .split_at_checked(size_of::<Pubkey>())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let identity =
+                    Pubkey::try_from(identity).map_err(|_| ProgramError::InvalidInstructionData)?;
+                let overrides = rest
+                    .get(..size_of::<WeightingConfigOverride>())
+                    .and_then(|slice| {
+                        bytemuck::try_from_bytes::<WeightingConfigOverride>(slice).ok()
+                    })
+                    .copied()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetOverride {
+                    identity,
+                    overrides,
+                }
+            }
+            21 => Self::ClearOverride,
+            22 | 23 => {
+                let identity = rest
+                    .get(..size_of::<Pubkey>())
+                    .and_then(|slice| Pubkey::try_from(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                if tag == 22 {
+                    Self::AddToList { identity }
+                } else {
+                    Self::RemoveFromList { identity }
+                }
+            }
+            24 => {
+                let &mode = rest.first().ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetListMode { mode }
+            }
+            25 | 42 => {
+                let min_multiplier_bps = rest
+                    .get(..U32_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let max_multiplier_bps = rest
+                    .get(U32_BYTES..2 * U32_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                if tag == 25 {
+                    Self::CreateMultiplierTable {
+                        min_multiplier_bps,
+                        max_multiplier_bps,
+                    }
+                } else {
+                    Self::SetMultiplierRange {
+                        min_multiplier_bps,
+                        max_multiplier_bps,
+                    }
+                }
+            }
+            26 => {
+                let (&num_entries, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let entries = rest
+                    .get(..usize::from(num_entries).saturating_mul(size_of::<MultiplierEntry>()))
+                    .and_then(|slice| bytemuck::try_cast_slice(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetMultipliers { entries }
+            }
+            27 => {
+                let (&num_identities, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let identities = rest
+                    .get(..usize::from(num_identities).saturating_mul(size_of::<Pubkey>()))
+                    .and_then(|slice| bytemuck::try_cast_slice(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::RemoveMultipliers { identities }
+            }
+            28 => {
+                let complete = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+
+                Self::SetComplete { complete }
+            }
+            29 => Self::BeginStagedWrite,
+            30 => {
+                let offset = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (length, data) = rest[U64_BYTES..]
+                    .split_at_checked(U32_BYTES)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let length = u32::from_le_bytes(
+                    length
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                ) as usize;
+
+                Self::StageChunk {
+                    offset,
+                    data: data
+                        .get(..length)
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                }
+            }
+            31 => {
+                let expected_hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::CommitStagedWrite { expected_hash }
+            }
+            32 => Self::AbortStagedWrite,
+            33 => {
+                let (writer, rest) = rest
+                    .split_at_checked(size_of::<Pubkey>())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let writer =
+                    Pubkey::try_from(writer).map_err(|_| ProgramError::InvalidInstructionData)?;
+                let allowed = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+
+                Self::SetWriter { writer, allowed }
+            }
+            34 => {
+                let emergency_authority = rest
+                    .get(..size_of::<Pubkey>())
+                    .and_then(|slice| Pubkey::try_from(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetEmergencyAuthority {
+                    emergency_authority,
+                }
+            }
+            35 => Self::EmergencyStatic,
+            36 => {
+                let min_slots = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetMinSlotsBetweenWrites { min_slots }
+            }
+            37 | 38 => {
+                let min_tc_ms = rest
+                    .get(..U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let max_tc_ms = rest
+                    .get(U64_BYTES..2 * U64_BYTES)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let allowed_modes = *rest
+                    .get(2 * U64_BYTES)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                if tag == 37 {
+                    Self::CreateBounds {
+                        min_tc_ms,
+                        max_tc_ms,
+                        allowed_modes,
+                    }
+                } else {
+                    Self::UpdateBounds {
+                        min_tc_ms,
+                        max_tc_ms,
+                        allowed_modes,
+                    }
+                }
+            }
+            39 => {
+                let bounds = rest
+                    .get(..size_of::<Pubkey>())
+                    .and_then(|slice| Pubkey::try_from(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::SetBounds { bounds }
+            }
+            40 => Self::Migrate,
+            41 => {
+                let identity = rest
+                    .get(..size_of::<Pubkey>())
+                    .and_then(|slice| Pubkey::try_from(slice).ok())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+
+                Self::GetEffectiveConfig { identity }
+            }
+            43 => Self::CloseMultiplierTable,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    /// Packs a [`RecordInstruction`] into a byte buffer, failing if its
+    /// variable-length data is too long for its length prefix.
+    pub fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match self {
+            Self::Initialize => buf.push(0),
+            Self::Write { offset, data } => {
+                buf.push(1);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&pack_len::<u32>(data.len())?.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::SetAuthority => buf.push(2),
+            Self::CloseAccount => buf.push(3),
+            Self::Reallocate { data_length } => {
+                buf.push(4);
+                buf.extend_from_slice(&data_length.to_le_bytes());
+            }
+            Self::SetWeightingConfig { config } => {
+                buf.push(5);
+                buf.extend_from_slice(bytemuck::bytes_of(config));
+            }
+            Self::ScheduleWeightingConfig {
+                config,
+                activation_epoch,
+            } => {
+                buf.push(6);
+                buf.extend_from_slice(bytemuck::bytes_of(config));
+                buf.extend_from_slice(&activation_epoch.to_le_bytes());
+            }
+            Self::ProposeAuthority => buf.push(7),
+            Self::AcceptAuthority => buf.push(8),
+            Self::CancelAuthorityTransfer => buf.push(9),
+            Self::SetSigners { threshold, signers } => {
+                buf.push(10);
+                buf.push(*threshold);
+                buf.push(pack_len(signers.len())?);
+                buf.extend_from_slice(bytemuck::cast_slice(signers));
+            }
+            Self::CreateProposal {
+                expiry_slot,
+                offset,
+                data,
+            } => {
+                buf.push(11);
+                buf.extend_from_slice(&expiry_slot.to_le_bytes());
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&pack_len::<u32>(data.len())?.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::ApproveProposal => buf.push(12),
+            Self::ExecuteProposal => buf.push(13),
+            Self::CancelProposal => buf.push(14),
+            Self::SetHistoryCapacity { capacity } => {
+                buf.push(15);
+                buf.push(*capacity);
+            }
+            Self::Rollback { versions_back } => {
+                buf.push(16);
+                buf.push(*versions_back);
+            }
+            Self::WriteIfSequence {
+                expected_sequence,
+                offset,
+                data,
+            } => {
+                buf.push(17);
+                buf.extend_from_slice(&expected_sequence.to_le_bytes());
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&pack_len::<u32>(data.len())?.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::CreateConfig { name } => {
+                buf.push(18);
+                buf.push(pack_len(name.len())?);
+                buf.extend_from_slice(name.as_bytes());
+            }
+            Self::Freeze => buf.push(19),
+            Self::SetOverride {
+                identity,
+                overrides,
+            } => {
+                buf.push(20);
+                buf.extend_from_slice(identity.as_ref());
+                buf.extend_from_slice(bytemuck::bytes_of(overrides));
+            }
+            Self::ClearOverride => buf.push(21),
+            Self::AddToList { identity } => {
+                buf.push(22);
+                buf.extend_from_slice(identity.as_ref());
+            }
+            Self::RemoveFromList { identity } => {
+                buf.push(23);
+                buf.extend_from_slice(identity.as_ref());
+            }
+            Self::SetListMode { mode } => {
+                buf.push(24);
+                buf.push(*mode);
+            }
+            Self::CreateMultiplierTable {
+                min_multiplier_bps,
+                max_multiplier_bps,
+            } => {
+                buf.push(25);
+                buf.extend_from_slice(&min_multiplier_bps.to_le_bytes());
+                buf.extend_from_slice(&max_multiplier_bps.to_le_bytes());
+            }
+            Self::SetMultipliers { entries } => {
+                buf.push(26);
+                buf.push(pack_len(entries.len())?);
+                buf.extend_from_slice(bytemuck::cast_slice(entries));
+            }
+            Self::RemoveMultipliers { identities } => {
+                buf.push(27);
+                buf.push(pack_len(identities.len())?);
+                buf.extend_from_slice(bytemuck::cast_slice(identities));
+            }
+            Self::SetComplete { complete } => {
+                buf.push(28);
+                buf.push(u8::from(*complete));
+            }
+            Self::BeginStagedWrite => buf.push(29),
+            Self::StageChunk { offset, data } => {
+                buf.push(30);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&pack_len::<u32>(data.len())?.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::CommitStagedWrite { expected_hash } => {
+                buf.push(31);
+                buf.extend_from_slice(expected_hash);
+            }
+            Self::AbortStagedWrite => buf.push(32),
+            Self::SetWriter { writer, allowed } => {
+                buf.push(33);
+                buf.extend_from_slice(writer.as_ref());
+                buf.push(u8::from(*allowed));
+            }
+            Self::SetEmergencyAuthority {
+                emergency_authority,
+            } => {
+                buf.push(34);
+                buf.extend_from_slice(emergency_authority.as_ref());
+            }
+            Self::EmergencyStatic => buf.push(35),
+            Self::SetMinSlotsBetweenWrites { min_slots } => {
+                buf.push(36);
+                buf.extend_from_slice(&min_slots.to_le_bytes());
+            }
+            Self::CreateBounds {
+                min_tc_ms,
+                max_tc_ms,
+                allowed_modes,
+            } => {
+                buf.push(37);
+                buf.extend_from_slice(&min_tc_ms.to_le_bytes());
+                buf.extend_from_slice(&max_tc_ms.to_le_bytes());
+                buf.push(*allowed_modes);
+            }
+            Self::UpdateBounds {
+                min_tc_ms,
+                max_tc_ms,
+                allowed_modes,
+            } => {
+                buf.push(38);
+                buf.extend_from_slice(&min_tc_ms.to_le_bytes());
+                buf.extend_from_slice(&max_tc_ms.to_le_bytes());
+                buf.push(*allowed_modes);
+            }
+            Self::SetBounds { bounds } => {
+                buf.push(39);
+                buf.extend_from_slice(bounds.as_ref());
+            }
+            Self::Migrate => buf.push(40),
+            Self::GetEffectiveConfig { identity } => {
+                buf.push(41);
+                buf.extend_from_slice(identity.as_ref());
+            }
+            Self::SetMultiplierRange {
+                min_multiplier_bps,
+                max_multiplier_bps,
+            } => {
+                buf.push(42);
+                buf.extend_from_slice(&min_multiplier_bps.to_le_bytes());
+                buf.extend_from_slice(&max_multiplier_bps.to_le_bytes());
+            }
+            Self::CloseMultiplierTable => buf.push(43),
+        };
+        Ok(buf)
+    }
+
+    /// Packs a [`RecordInstruction`] without variable-length data, which
+    /// always fits.
+    fn pack_fixed(&self) -> Vec<u8> {
+        self.pack()
+            .expect("instruction without variable-length data always packs")
+    }
+}
+
+/// Convert a length to the type of its prefix in the instruction data
+fn pack_len<T: TryFrom<usize>>(len: usize) -> Result<T, ProgramError> {
+    T::try_from(len).map_err(|_| ProgramError::InvalidArgument)
+}
 
 /// Create a `RecordInstruction::Initialize` instruction
 pub fn initialize(record_account: &Pubkey, authority: &Pubkey) -> Instruction {
@@ -139,75 +1220,850 @@ pub fn initialize(record_account: &Pubkey, authority: &Pubkey) -> Instruction {
         program_id: id(),
         accounts: vec![
             AccountMeta::new(*record_account, false),
-            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new_readonly(*authority, false),
+        ],
+        data: RecordInstruction::Initialize.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::Write` instruction
+pub fn write(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    data: &[u8],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Write { offset, data }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::SetAuthority` instruction
+pub fn set_authority(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*new_authority, false),
+        ],
+        data: RecordInstruction::SetAuthority.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::CloseAccount` instruction
+pub fn close_account(record_account: &Pubkey, signer: &Pubkey, receiver: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::CloseAccount.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::Reallocate` instruction
+pub fn reallocate(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    receiver: &Pubkey,
+    data_length: u64,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*receiver, false),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::Reallocate { data_length }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetWeightingConfig` instruction
+pub fn set_weighting_config(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    config: &WeightingConfig,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::SetWeightingConfig { config: *config }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::ScheduleWeightingConfig` instruction
+pub fn schedule_weighting_config(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    config: &WeightingConfig,
+    activation_epoch: u64,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::ScheduleWeightingConfig {
+            config: *config,
+            activation_epoch,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::ProposeAuthority` instruction
+pub fn propose_authority(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*new_authority, false),
+        ],
+        data: RecordInstruction::ProposeAuthority.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::AcceptAuthority` instruction
+pub fn accept_authority(record_account: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*new_authority, true),
+        ],
+        data: RecordInstruction::AcceptAuthority.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::CancelAuthorityTransfer` instruction
+pub fn cancel_authority_transfer(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::CancelAuthorityTransfer.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetSigners` instruction
+pub fn set_signers(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    threshold: u8,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::SetSigners { threshold, signers }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::CreateProposal` instruction
+pub fn create_proposal(
+    proposal_account: &Pubkey,
+    record_account: &Pubkey,
+    proposer: &Pubkey,
+    expiry_slot: u64,
+    offset: u64,
+    data: &[u8],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*proposal_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*proposer, true),
+        ],
+        data: RecordInstruction::CreateProposal {
+            expiry_slot,
+            offset,
+            data,
+        }
+        .pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::ApproveProposal` instruction
+pub fn approve_proposal(
+    proposal_account: &Pubkey,
+    record_account: &Pubkey,
+    approver: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*proposal_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*approver, true),
+        ],
+        data: RecordInstruction::ApproveProposal.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::ExecuteProposal` instruction
+pub fn execute_proposal(proposal_account: &Pubkey, record_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*proposal_account, false),
+            AccountMeta::new(*record_account, false),
+        ],
+        data: RecordInstruction::ExecuteProposal.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::CancelProposal` instruction
+pub fn cancel_proposal(
+    proposal_account: &Pubkey,
+    proposer: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*proposal_account, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::CancelProposal.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetHistoryCapacity` instruction
+pub fn set_history_capacity(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    capacity: u8,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::SetHistoryCapacity { capacity }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::Rollback` instruction
+pub fn rollback(record_account: &Pubkey, signer: &Pubkey, versions_back: u8) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Rollback { versions_back }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::WriteIfSequence` instruction
+pub fn write_if_sequence(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    expected_sequence: u64,
+    offset: u64,
+    data: &[u8],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::WriteIfSequence {
+            expected_sequence,
+            offset,
+            data,
+        }
+        .pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::CreateConfig` instruction for the config
+/// account of the given authority and profile name
+pub fn create_config(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    name: &str,
+) -> Result<Instruction, ProgramError> {
+    let (config_account, _) = crate::find_config_address(authority, name);
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(config_account, false),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::CreateConfig { name }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::Freeze` instruction
+pub fn freeze(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Freeze.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetOverride` instruction for the override
+/// account of the given record and validator identity
+pub fn set_override(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    identity: &Pubkey,
+    overrides: &WeightingConfigOverride,
+) -> Instruction {
+    let (override_account, _) = crate::find_override_address(record_account, identity);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(override_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::SetOverride {
+            identity: *identity,
+            overrides: *overrides,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::ClearOverride` instruction for the override
+/// account of the given record and validator identity
+pub fn clear_override(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+    identity: &Pubkey,
+) -> Instruction {
+    let (override_account, _) = crate::find_override_address(record_account, identity);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(override_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::ClearOverride.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::AddToList` instruction
+pub fn add_to_list(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    identity: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::AddToList {
+            identity: *identity,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::RemoveFromList` instruction
+pub fn remove_from_list(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+    identity: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::RemoveFromList {
+            identity: *identity,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetListMode` instruction
+pub fn set_list_mode(record_account: &Pubkey, signer: &Pubkey, mode: ListMode) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::SetListMode { mode: mode as u8 }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::CreateMultiplierTable` instruction for the
+/// multiplier table of the given record
+pub fn create_multiplier_table(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    min_multiplier_bps: u32,
+    max_multiplier_bps: u32,
+) -> Instruction {
+    let (table_account, _) = crate::find_multiplier_table_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(table_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::CreateMultiplierTable {
+            min_multiplier_bps,
+            max_multiplier_bps,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetMultipliers` instruction for the
+/// multiplier table of the given record
+pub fn set_multipliers(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    entries: &[MultiplierEntry],
+) -> Result<Instruction, ProgramError> {
+    let (table_account, _) = crate::find_multiplier_table_address(record_account);
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(table_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::SetMultipliers { entries }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::RemoveMultipliers` instruction for the
+/// multiplier table of the given record
+pub fn remove_multipliers(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+    identities: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let (table_account, _) = crate::find_multiplier_table_address(record_account);
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(table_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::RemoveMultipliers { identities }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::SetMultiplierRange` instruction for the
+/// multiplier table of the given record
+pub fn set_multiplier_range(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    min_multiplier_bps: u32,
+    max_multiplier_bps: u32,
+) -> Instruction {
+    let (table_account, _) = crate::find_multiplier_table_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(table_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::SetMultiplierRange {
+            min_multiplier_bps,
+            max_multiplier_bps,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::CloseMultiplierTable` instruction for the
+/// multiplier table of the given record
+pub fn close_multiplier_table(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (table_account, _) = crate::find_multiplier_table_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(table_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::CloseMultiplierTable.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetComplete` instruction
+pub fn set_complete(record_account: &Pubkey, signer: &Pubkey, complete: bool) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::SetComplete { complete }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::BeginStagedWrite` instruction for the staging
+/// account of the given record
+pub fn begin_staged_write(record_account: &Pubkey, signer: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (staging_account, _) = crate::find_staging_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(staging_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
+        ],
+        data: RecordInstruction::BeginStagedWrite.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::StageChunk` instruction for the staging
+/// account of the given record
+pub fn stage_chunk(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    data: &[u8],
+) -> Result<Instruction, ProgramError> {
+    let (staging_account, _) = crate::find_staging_address(record_account);
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(staging_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::StageChunk { offset, data }.pack()?,
+    })
+}
+
+/// Create a `RecordInstruction::CommitStagedWrite` instruction for the
+/// staging account of the given record
+pub fn commit_staged_write(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+    expected_hash: [u8; 32],
+) -> Instruction {
+    let (staging_account, _) = crate::find_staging_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(staging_account, false),
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
         ],
-        data: RecordInstruction::Initialize.pack(),
+        data: RecordInstruction::CommitStagedWrite { expected_hash }.pack_fixed(),
     }
 }
 
-/// Create a `RecordInstruction::Write` instruction
-pub fn write(record_account: &Pubkey, signer: &Pubkey, offset: u64, data: &[u8]) -> Instruction {
+/// Create a `RecordInstruction::AbortStagedWrite` instruction for the
+/// staging account of the given record
+pub fn abort_staged_write(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (staging_account, _) = crate::find_staging_address(record_account);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(staging_account, false),
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::AbortStagedWrite.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetWriter` instruction
+pub fn set_writer(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    writer: &Pubkey,
+    allowed: bool,
+) -> Instruction {
     Instruction {
         program_id: id(),
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
         ],
-        data: RecordInstruction::Write { offset, data }.pack(),
+        data: RecordInstruction::SetWriter {
+            writer: *writer,
+            allowed,
+        }
+        .pack_fixed(),
     }
 }
 
-/// Create a `RecordInstruction::SetAuthority` instruction
-pub fn set_authority(
+/// Create a `RecordInstruction::SetEmergencyAuthority` instruction
+pub fn set_emergency_authority(
     record_account: &Pubkey,
     signer: &Pubkey,
-    new_authority: &Pubkey,
+    emergency_authority: &Pubkey,
 ) -> Instruction {
     Instruction {
         program_id: id(),
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
-            AccountMeta::new_readonly(*new_authority, false),
         ],
-        data: RecordInstruction::SetAuthority.pack(),
+        data: RecordInstruction::SetEmergencyAuthority {
+            emergency_authority: *emergency_authority,
+        }
+        .pack_fixed(),
     }
 }
 
-/// Create a `RecordInstruction::CloseAccount` instruction
-pub fn close_account(record_account: &Pubkey, signer: &Pubkey, receiver: &Pubkey) -> Instruction {
+/// Create a `RecordInstruction::EmergencyStatic` instruction
+pub fn emergency_static(record_account: &Pubkey, emergency_authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*emergency_authority, true),
+        ],
+        data: RecordInstruction::EmergencyStatic.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetMinSlotsBetweenWrites` instruction
+pub fn set_min_slots_between_writes(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    min_slots: u64,
+) -> Instruction {
     Instruction {
         program_id: id(),
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
-            AccountMeta::new(*receiver, false),
         ],
-        data: RecordInstruction::CloseAccount.pack(),
+        data: RecordInstruction::SetMinSlotsBetweenWrites { min_slots }.pack_fixed(),
     }
 }
 
-/// Create a `RecordInstruction::Reallocate` instruction
-pub fn reallocate(record_account: &Pubkey, signer: &Pubkey, data_length: u64) -> Instruction {
+/// Create a `RecordInstruction::CreateBounds` instruction
+pub fn create_bounds(
+    bounds_account: &Pubkey,
+    governance_authority: &Pubkey,
+    min_tc_ms: u64,
+    max_tc_ms: u64,
+    allowed_modes: u8,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*bounds_account, false),
+            AccountMeta::new_readonly(*governance_authority, true),
+        ],
+        data: RecordInstruction::CreateBounds {
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::UpdateBounds` instruction
+pub fn update_bounds(
+    bounds_account: &Pubkey,
+    governance_authority: &Pubkey,
+    min_tc_ms: u64,
+    max_tc_ms: u64,
+    allowed_modes: u8,
+) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*bounds_account, false),
+            AccountMeta::new_readonly(*governance_authority, true),
+        ],
+        data: RecordInstruction::UpdateBounds {
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::SetBounds` instruction
+pub fn set_bounds(record_account: &Pubkey, signer: &Pubkey, bounds: &Pubkey) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*record_account, false),
+        AccountMeta::new_readonly(*signer, true),
+    ];
+    if *bounds != Pubkey::default() {
+        accounts.push(AccountMeta::new_readonly(*bounds, false));
+    }
+    Instruction {
+        program_id: id(),
+        accounts,
+        data: RecordInstruction::SetBounds { bounds: *bounds }.pack_fixed(),
+    }
+}
+
+/// Create a `RecordInstruction::Migrate` instruction
+pub fn migrate(record_account: &Pubkey, signer: &Pubkey, payer: &Pubkey) -> Instruction {
     Instruction {
         program_id: id(),
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::id(), false),
         ],
-        data: RecordInstruction::Reallocate { data_length }.pack(),
+        data: RecordInstruction::Migrate.pack_fixed(),
     }
 }
 
+/// Create a `RecordInstruction::GetEffectiveConfig` instruction for the
+/// given record and validator identity
+pub fn get_effective_config(record_account: &Pubkey, identity: &Pubkey) -> Instruction {
+    let (override_account, _) = crate::find_override_address(record_account, identity);
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(override_account, false),
+        ],
+        data: RecordInstruction::GetEffectiveConfig {
+            identity: *identity,
+        }
+        .pack_fixed(),
+    }
+}
+
+/// Append the bounds account referenced by a record to an instruction
+/// writing its config
+pub fn with_bounds(mut instruction: Instruction, bounds: &Pubkey) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*bounds, false));
+    instruction
+}
+
+/// Append the bounds account referenced by a record and its governance
+/// authority as a signer to a `SetBounds` instruction removing or replacing
+/// them
+pub fn with_bounds_authority(
+    instruction: Instruction,
+    bounds: &Pubkey,
+    governance_authority: &Pubkey,
+) -> Instruction {
+    let mut instruction = with_bounds(instruction, bounds);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*governance_authority, true));
+    instruction
+}
+
+/// Append additional signers from a record's signer set to an instruction
+/// approved by the record authority
+pub fn with_additional_signers(
+    mut instruction: Instruction,
+    additional_signers: &[&Pubkey],
+) -> Instruction {
+    instruction.accounts.extend(
+        additional_signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    instruction
+}
+
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::state::tests::TEST_BYTES, solana_program_error::ProgramError};
+    use {super::*, crate::state::WeightingMode, solana_program_error::ProgramError};
+
+    const TEST_BYTES: [u8; 8] = [42; 8];
+    const TEST_TC_MS: u64 = 30_000;
 
     #[test]
     fn serialize_initialize() {
         let instruction = RecordInstruction::Initialize;
         let expected = vec![0];
-        assert_eq!(instruction.pack(), expected);
+        assert_eq!(instruction.pack().unwrap(), expected);
         assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
     }
 
@@ -220,7 +2076,7 @@ mod tests {
         expected.extend_from_slice(&offset.to_le_bytes());
         expected.extend_from_slice(&(data.len() as u32).to_le_bytes());
         expected.extend_from_slice(data);
-        assert_eq!(instruction.pack(), expected);
+        assert_eq!(instruction.pack().unwrap(), expected);
         assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
     }
 
@@ -228,7 +2084,7 @@ mod tests {
     fn serialize_set_authority() {
         let instruction = RecordInstruction::SetAuthority;
         let expected = vec![2];
-        assert_eq!(instruction.pack(), expected);
+        assert_eq!(instruction.pack().unwrap(), expected);
         assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
     }
 
@@ -236,7 +2092,7 @@ mod tests {
     fn serialize_close_account() {
         let instruction = RecordInstruction::CloseAccount;
         let expected = vec![3];
-        assert_eq!(instruction.pack(), expected);
+        assert_eq!(instruction.pack().unwrap(), expected);
         assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
     }
 
@@ -246,13 +2102,422 @@ mod tests {
         let instruction = RecordInstruction::Reallocate { data_length };
         let mut expected = vec![4];
         expected.extend_from_slice(&data_length.to_le_bytes());
-        assert_eq!(instruction.pack(), expected);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_weighting_config() {
+        let config = WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS);
+        let instruction = RecordInstruction::SetWeightingConfig { config };
+        let mut expected = vec![5];
+        expected.extend_from_slice(bytemuck::bytes_of(&config));
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_schedule_weighting_config() {
+        let config = WeightingConfig::new(WeightingMode::Static, TEST_TC_MS);
+        let activation_epoch = 42u64;
+        let instruction = RecordInstruction::ScheduleWeightingConfig {
+            config,
+            activation_epoch,
+        };
+        let mut expected = vec![6];
+        expected.extend_from_slice(bytemuck::bytes_of(&config));
+        expected.extend_from_slice(&activation_epoch.to_le_bytes());
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_propose_authority() {
+        let instruction = RecordInstruction::ProposeAuthority;
+        let expected = vec![7];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_accept_authority() {
+        let instruction = RecordInstruction::AcceptAuthority;
+        let expected = vec![8];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_cancel_authority_transfer() {
+        let instruction = RecordInstruction::CancelAuthorityTransfer;
+        let expected = vec![9];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_signers() {
+        let signers = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        let instruction = RecordInstruction::SetSigners {
+            threshold: 2,
+            signers: &signers,
+        };
+        let mut expected = vec![10, 2, 2];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&[2; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_signers_fail_too_many() {
+        let signers = [Pubkey::new_from_array([1; 32]); 256];
+        let instruction = RecordInstruction::SetSigners {
+            threshold: 1,
+            signers: &signers,
+        };
+        assert_eq!(instruction.pack(), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            set_signers(&Pubkey::new_unique(), &Pubkey::new_unique(), 1, &signers),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn serialize_create_proposal() {
+        let data = &TEST_BYTES;
+        let expiry_slot = 100u64;
+        let offset = 8u64;
+        let instruction = RecordInstruction::CreateProposal {
+            expiry_slot,
+            offset,
+            data,
+        };
+        let mut expected = vec![11];
+        expected.extend_from_slice(&expiry_slot.to_le_bytes());
+        expected.extend_from_slice(&offset.to_le_bytes());
+        expected.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        expected.extend_from_slice(data);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_proposal_lifecycle() {
+        for (instruction, tag) in [
+            (RecordInstruction::ApproveProposal, 12),
+            (RecordInstruction::ExecuteProposal, 13),
+            (RecordInstruction::CancelProposal, 14),
+        ] {
+            let expected = vec![tag];
+            assert_eq!(instruction.pack().unwrap(), expected);
+            assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+        }
+    }
+
+    #[test]
+    fn serialize_set_history_capacity() {
+        let instruction = RecordInstruction::SetHistoryCapacity { capacity: 16 };
+        let expected = vec![15, 16];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_rollback() {
+        let instruction = RecordInstruction::Rollback { versions_back: 1 };
+        let expected = vec![16, 1];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_write_if_sequence() {
+        let data = &TEST_BYTES;
+        let expected_sequence = 3u64;
+        let offset = 8u64;
+        let instruction = RecordInstruction::WriteIfSequence {
+            expected_sequence,
+            offset,
+            data,
+        };
+        let mut expected = vec![17];
+        expected.extend_from_slice(&expected_sequence.to_le_bytes());
+        expected.extend_from_slice(&offset.to_le_bytes());
+        expected.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        expected.extend_from_slice(data);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_create_config() {
+        let instruction = RecordInstruction::CreateConfig { name: "mainnet" };
+        let mut expected = vec![18, 7];
+        expected.extend_from_slice(b"mainnet");
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_create_config_fail_name_too_long() {
+        let name = "a".repeat(256);
+        let instruction = RecordInstruction::CreateConfig { name: &name };
+        assert_eq!(instruction.pack(), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn deserialize_invalid_config_name() {
+        let expected = vec![18, 2, 0xff, 0xfe];
+        let err: ProgramError = RecordInstruction::unpack(&expected).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidInstructionData);
+    }
+
+    #[test]
+    fn serialize_freeze() {
+        let instruction = RecordInstruction::Freeze;
+        let expected = vec![19];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_override() {
+        let identity = Pubkey::new_from_array([1; 32]);
+        let overrides = WeightingConfigOverride {
+            fields: WeightingConfigOverride::TC_MS,
+            config: WeightingConfig::new(WeightingMode::Dynamic, TEST_TC_MS),
+        };
+        let instruction = RecordInstruction::SetOverride {
+            identity,
+            overrides,
+        };
+        let mut expected = vec![20];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(bytemuck::bytes_of(&overrides));
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_clear_override() {
+        let instruction = RecordInstruction::ClearOverride;
+        let expected = vec![21];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_list_instructions() {
+        let identity = Pubkey::new_from_array([1; 32]);
+        for (instruction, tag) in [
+            (RecordInstruction::AddToList { identity }, 22),
+            (RecordInstruction::RemoveFromList { identity }, 23),
+        ] {
+            let mut expected = vec![tag];
+            expected.extend_from_slice(&[1; 32]);
+            assert_eq!(instruction.pack().unwrap(), expected);
+            assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+        }
+
+        let instruction = RecordInstruction::SetListMode {
+            mode: ListMode::Allow as u8,
+        };
+        let expected = vec![24, 1];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_multiplier_instructions() {
+        let instruction = RecordInstruction::CreateMultiplierTable {
+            min_multiplier_bps: 5_000,
+            max_multiplier_bps: 20_000,
+        };
+        let mut expected = vec![25];
+        expected.extend_from_slice(&5_000u32.to_le_bytes());
+        expected.extend_from_slice(&20_000u32.to_le_bytes());
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let entries = [MultiplierEntry::new(
+            Pubkey::new_from_array([1; 32]),
+            15_000,
+        )];
+        let instruction = RecordInstruction::SetMultipliers { entries: &entries };
+        let mut expected = vec![26, 1];
+        expected.extend_from_slice(&[1; 32]);
+        expected.extend_from_slice(&15_000u32.to_le_bytes());
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let identities = [Pubkey::new_from_array([1; 32])];
+        let instruction = RecordInstruction::RemoveMultipliers {
+            identities: &identities,
+        };
+        let mut expected = vec![27, 1];
+        expected.extend_from_slice(&[1; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let instruction = RecordInstruction::SetMultiplierRange {
+            min_multiplier_bps: 5_000,
+            max_multiplier_bps: 20_000,
+        };
+        let mut expected = vec![42];
+        expected.extend_from_slice(&5_000u32.to_le_bytes());
+        expected.extend_from_slice(&20_000u32.to_le_bytes());
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let instruction = RecordInstruction::CloseMultiplierTable;
+        let expected = vec![43];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_complete() {
+        for (complete, byte) in [(false, 0), (true, 1)] {
+            let instruction = RecordInstruction::SetComplete { complete };
+            let expected = vec![28, byte];
+            assert_eq!(instruction.pack().unwrap(), expected);
+            assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+        }
+        let err: ProgramError = RecordInstruction::unpack(&[28, 2]).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidInstructionData);
+    }
+
+    #[test]
+    fn serialize_staged_write_instructions() {
+        let data = &TEST_BYTES;
+        let offset = 8u64;
+        let instruction = RecordInstruction::StageChunk { offset, data };
+        let mut expected = vec![30];
+        expected.extend_from_slice(&offset.to_le_bytes());
+        expected.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        expected.extend_from_slice(data);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let instruction = RecordInstruction::CommitStagedWrite {
+            expected_hash: [7; 32],
+        };
+        let mut expected = vec![31];
+        expected.extend_from_slice(&[7; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        for (instruction, tag) in [
+            (RecordInstruction::BeginStagedWrite, 29),
+            (RecordInstruction::AbortStagedWrite, 32),
+        ] {
+            let expected = vec![tag];
+            assert_eq!(instruction.pack().unwrap(), expected);
+            assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+        }
+    }
+
+    #[test]
+    fn serialize_set_writer() {
+        let writer = Pubkey::new_from_array([1; 32]);
+        let instruction = RecordInstruction::SetWriter {
+            writer,
+            allowed: true,
+        };
+        let mut expected = vec![33];
+        expected.extend_from_slice(&[1; 32]);
+        expected.push(1);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_emergency_authority() {
+        let emergency_authority = Pubkey::new_from_array([2; 32]);
+        let instruction = RecordInstruction::SetEmergencyAuthority {
+            emergency_authority,
+        };
+        let mut expected = vec![34];
+        expected.extend_from_slice(&[2; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_emergency_static() {
+        let instruction = RecordInstruction::EmergencyStatic;
+        let expected = vec![35];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_set_min_slots_between_writes() {
+        let instruction = RecordInstruction::SetMinSlotsBetweenWrites { min_slots: 150 };
+        let mut expected = vec![36];
+        expected.extend_from_slice(&150u64.to_le_bytes());
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_bounds() {
+        let instruction = RecordInstruction::CreateBounds {
+            min_tc_ms: 1_000,
+            max_tc_ms: 60_000,
+            allowed_modes: 0b11,
+        };
+        let mut expected = vec![37];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.extend_from_slice(&60_000u64.to_le_bytes());
+        expected.push(0b11);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let instruction = RecordInstruction::UpdateBounds {
+            min_tc_ms: 1_000,
+            max_tc_ms: 60_000,
+            allowed_modes: 0b11,
+        };
+        expected[0] = 38;
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+
+        let instruction = RecordInstruction::SetBounds {
+            bounds: Pubkey::new_from_array([3; 32]),
+        };
+        let mut expected = vec![39];
+        expected.extend_from_slice(&[3; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_migrate() {
+        let instruction = RecordInstruction::Migrate;
+        let expected = vec![40];
+        assert_eq!(instruction.pack().unwrap(), expected);
+        assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
+    }
+
+    #[test]
+    fn serialize_get_effective_config() {
+        let instruction = RecordInstruction::GetEffectiveConfig {
+            identity: Pubkey::new_from_array([4; 32]),
+        };
+        let mut expected = vec![41];
+        expected.extend_from_slice(&[4; 32]);
+        assert_eq!(instruction.pack().unwrap(), expected);
         assert_eq!(RecordInstruction::unpack(&expected).unwrap(), instruction);
     }
 
     #[test]
     fn deserialize_invalid_instruction() {
-        let mut expected = vec![12];
+        let mut expected = vec![u8::MAX];
         expected.extend_from_slice(&TEST_BYTES);
         let err: ProgramError = RecordInstruction::unpack(&expected).unwrap_err();
         assert_eq!(err, ProgramError::InvalidInstructionData);
@@ -1,11 +1,101 @@
 //! Record program
 #![deny(missing_docs)]
 
+pub mod cpi;
 mod entrypoint;
 pub mod error;
 pub mod instruction;
 pub mod processor;
-pub mod state;
+mod syscall;
+
+// Account layouts and the program id live in the interface crate so that
+// clients and validator-side readers can share them
+pub use gossip_weight_controller_interface::{check_id, event, id, state, ID};
+
+use solana_pubkey::Pubkey;
+
+/// Seed prefix of config accounts addressed by authority and profile name
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Longest profile name of a config account, the limit of a single seed
+pub const MAX_CONFIG_NAME_LEN: usize = solana_pubkey::MAX_SEED_LEN;
+
+/// Find the address of the config account created by `CreateConfig` for the
+/// given authority and profile name
+pub fn find_config_address(authority: &Pubkey, name: &str) -> (Pubkey, u8) {
+    find_config_address_with_program_id(authority, name, &id())
+}
+
+pub(crate) fn find_config_address_with_program_id(
+    authority: &Pubkey,
+    name: &str,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CONFIG_SEED, authority.as_ref(), name.as_bytes()],
+        program_id,
+    )
+}
+
+/// Seed prefix of override accounts addressed by config account and validator
+/// identity
+pub const OVERRIDE_SEED: &[u8] = b"override";
+
+/// Find the address of the override account set by `SetOverride` for the
+/// given config account and validator identity
+pub fn find_override_address(config: &Pubkey, identity: &Pubkey) -> (Pubkey, u8) {
+    find_override_address_with_program_id(config, identity, &id())
+}
+
+pub(crate) fn find_override_address_with_program_id(
+    config: &Pubkey,
+    identity: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[OVERRIDE_SEED, config.as_ref(), identity.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of the multiplier table account of a config account
+pub const MULTIPLIER_TABLE_SEED: &[u8] = b"multipliers";
+
+/// Find the address of the multiplier table created by
+/// `CreateMultiplierTable` for the given config account
+pub fn find_multiplier_table_address(config: &Pubkey) -> (Pubkey, u8) {
+    find_multiplier_table_address_with_program_id(config, &id())
+}
+
+pub(crate) fn find_multiplier_table_address_with_program_id(
+    config: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MULTIPLIER_TABLE_SEED, config.as_ref()], program_id)
+}
+
+/// Seed prefix of the staging account of a record
+pub const STAGING_SEED: &[u8] = b"staging";
+
+/// Find the address of the staging account created by `BeginStagedWrite` for
+/// the given record account
+pub fn find_staging_address(record: &Pubkey) -> (Pubkey, u8) {
+    find_staging_address_with_program_id(record, &id())
+}
+
+pub(crate) fn find_staging_address_with_program_id(
+    record: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAGING_SEED, record.as_ref()], program_id)
+}
+
+/// Check if a validator identity is on the identity list of a record account
+/// holding a weighting config, by a binary search of the sorted list
+pub fn is_listed(account_data: &[u8], identity: &Pubkey) -> bool {
+    state::RecordData::unpack_identity_list(account_data)
+        .is_some_and(|list| list.binary_search(identity).is_ok())
+}
 
 // Export current SDK types for downstream users building with a different SDK
 // version
@@ -13,5 +103,3 @@ pub use {
     solana_account_info, solana_decode_error, solana_instruction, solana_msg,
     solana_program_entrypoint, solana_program_error, solana_program_pack, solana_pubkey,
 };
-
-solana_pubkey::declare_id!("recr1L3PCGKLbckBqMNcJhuuyU1zgo8nBhfLVsJNwr5");
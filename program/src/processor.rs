@@ -1,12 +1,33 @@
 //! Program state processor
 
 use {
-    crate::{error::RecordError, instruction::RecordInstruction, state::RecordData},
+    crate::{
+        error::RecordError,
+        event::{
+            AuthorityChangeEvent, CloseEvent, EventHeader, EventType, InitializeEvent,
+            ReallocateEvent, SignersChangeEvent, WriteEvent,
+        },
+        find_config_address_with_program_id, find_multiplier_table_address_with_program_id,
+        find_override_address_with_program_id, find_staging_address_with_program_id,
+        instruction::RecordInstruction,
+        state::{
+            resolve_config, AccountType, BoundsData, HistoryEntry, ListMode, MultiplierEntry,
+            MultiplierTableData, OverrideData, ProposalData, RecordData, RecordDataV1,
+            RecordSchema, StagingData, WeightingConfig, WeightingConfigOverride,
+            WeightingConfigState, WeightingMode,
+        },
+        syscall::{invoke, invoke_signed, log_data, set_return_data},
+        CONFIG_SEED, MAX_CONFIG_NAME_LEN, MULTIPLIER_TABLE_SEED, OVERRIDE_SEED, STAGING_SEED,
+    },
+    num_traits::FromPrimitive,
     solana_account_info::{next_account_info, AccountInfo},
+    solana_clock::Clock,
     solana_msg::msg,
     solana_program_error::{ProgramError, ProgramResult},
-    solana_program_pack::IsInitialized,
     solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    solana_system_interface::instruction as system_instruction,
+    solana_sysvar::Sysvar,
 };
 
 fn check_authority(authority_info: &AccountInfo, expected_authority: &Pubkey) -> ProgramResult {
@@ -21,13 +42,802 @@ fn check_authority(authority_info: &AccountInfo, expected_authority: &Pubkey) ->
     Ok(())
 }
 
+/// Check that a change to the record is approved. Records with a signer set
+/// need `threshold` distinct listed signers, found among the authority account
+/// and any accounts following the ones the instruction expects.
+fn check_record_authority<'a>(
+    account_data: &RecordData,
+    authority_info: &AccountInfo<'a>,
+    additional_signer_infos: &[AccountInfo<'a>],
+) -> ProgramResult {
+    if !account_data.is_multisig() {
+        return check_authority(authority_info, &account_data.authority);
+    }
+    let signers = account_data.signers();
+    let mut approved = [false; RecordData::MAX_SIGNERS];
+    for signer_info in std::iter::once(authority_info).chain(additional_signer_infos) {
+        if !signer_info.is_signer {
+            continue;
+        }
+        if let Some(index) = signers.iter().position(|signer| signer == signer_info.key) {
+            approved[index] = true;
+        }
+    }
+    let num_approved = approved.iter().filter(|approved| **approved).count();
+    if num_approved < usize::from(account_data.threshold) {
+        msg!(
+            "{} of {} required signatures provided",
+            num_approved,
+            account_data.threshold
+        );
+        return Err(RecordError::NotEnoughSigners.into());
+    }
+    Ok(())
+}
+
+/// Check that a write to the config is approved, by one of the record's
+/// writers or by the record authority, returning whether the record
+/// authority approved it
+fn check_writer_authority<'a>(
+    account_data: &RecordData,
+    authority_info: &AccountInfo<'a>,
+    additional_signer_infos: &[AccountInfo<'a>],
+) -> Result<bool, ProgramError> {
+    if authority_info.is_signer && account_data.writers().contains(authority_info.key) {
+        return Ok(!account_data.is_multisig() && *authority_info.key == account_data.authority);
+    }
+    check_record_authority(account_data, authority_info, additional_signer_infos)?;
+    Ok(true)
+}
+
+fn check_not_frozen(account_data: &RecordData) -> ProgramResult {
+    if account_data.is_frozen() {
+        msg!("Record is frozen");
+        return Err(RecordError::AccountFrozen.into());
+    }
+    Ok(())
+}
+
+fn check_signer_set(signers: &[Pubkey], threshold: u8) -> ProgramResult {
+    if signers.len() > RecordData::MAX_SIGNERS {
+        msg!("At most {} signers allowed", RecordData::MAX_SIGNERS);
+        return Err(RecordError::InvalidSignerSet.into());
+    }
+    if usize::from(threshold) > signers.len() || (threshold == 0 && !signers.is_empty()) {
+        msg!(
+            "Invalid threshold {} for {} signers",
+            threshold,
+            signers.len()
+        );
+        return Err(RecordError::InvalidSignerSet.into());
+    }
+    for (index, signer) in signers.iter().enumerate() {
+        if signers[..index].contains(signer) {
+            msg!("Duplicate signer {}", signer);
+            return Err(RecordError::InvalidSignerSet.into());
+        }
+    }
+    Ok(())
+}
+
+fn check_weighting_config(config: &WeightingConfig) -> ProgramResult {
+    if config.weighting_mode().is_none() {
+        msg!("Unknown weighting mode {}", config.weighting_mode);
+        return Err(RecordError::InvalidWeightingMode.into());
+    }
+    let tc_ms = u64::from(config.tc_ms);
+    if !(WeightingConfig::MIN_TC_MS..=WeightingConfig::MAX_TC_MS).contains(&tc_ms) {
+        msg!("Time constant {} ms out of range", tc_ms);
+        return Err(RecordError::InvalidTimeConstant.into());
+    }
+    Ok(())
+}
+
+fn check_weighting_config_override(overrides: &WeightingConfigOverride) -> ProgramResult {
+    if overrides.fields & !WeightingConfigOverride::ALL_FIELDS != 0 {
+        msg!("Unknown override fields {:#x}", overrides.fields);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if overrides.overrides(WeightingConfigOverride::WEIGHTING_MODE)
+        && overrides.config.weighting_mode().is_none()
+    {
+        msg!("Unknown weighting mode {}", overrides.config.weighting_mode);
+        return Err(RecordError::InvalidWeightingMode.into());
+    }
+    let tc_ms = u64::from(overrides.config.tc_ms);
+    if overrides.overrides(WeightingConfigOverride::TC_MS)
+        && !(WeightingConfig::MIN_TC_MS..=WeightingConfig::MAX_TC_MS).contains(&tc_ms)
+    {
+        msg!("Time constant {} ms out of range", tc_ms);
+        return Err(RecordError::InvalidTimeConstant.into());
+    }
+    Ok(())
+}
+
+fn check_weighting_config_state(
+    state: &WeightingConfigState,
+    bounds: Option<&BoundsData>,
+) -> ProgramResult {
+    check_weighting_config(&state.active)?;
+    check_config_bounds(&state.active, bounds)?;
+    if state.activation_epoch().is_some() {
+        check_weighting_config(&state.pending)?;
+        check_config_bounds(&state.pending, bounds)?;
+    }
+    Ok(())
+}
+
+fn check_bounds_range(min_tc_ms: u64, max_tc_ms: u64, allowed_modes: u8) -> ProgramResult {
+    if min_tc_ms > max_tc_ms {
+        msg!(
+            "Time constant range {}..={} ms is empty",
+            min_tc_ms,
+            max_tc_ms
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    if allowed_modes == 0 || allowed_modes & !BoundsData::ALL_MODES != 0 {
+        msg!("Invalid allowed modes {:#04b}", allowed_modes);
+        return Err(RecordError::InvalidWeightingMode.into());
+    }
+    Ok(())
+}
+
+fn check_multiplier_range(min_multiplier_bps: u32, max_multiplier_bps: u32) -> ProgramResult {
+    if min_multiplier_bps > max_multiplier_bps {
+        msg!(
+            "Multiplier range {}..={} bps is empty",
+            min_multiplier_bps,
+            max_multiplier_bps
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+fn check_config_bounds(config: &WeightingConfig, bounds: Option<&BoundsData>) -> ProgramResult {
+    if let Some(bounds) = bounds.filter(|bounds| !bounds.accepts(config)) {
+        msg!(
+            "Config with mode {} and time constant {} ms outside bounds of {}..={} ms and modes {:#04b}",
+            config.weighting_mode,
+            u64::from(config.tc_ms),
+            u64::from(bounds.min_tc_ms),
+            u64::from(bounds.max_tc_ms),
+            bounds.allowed_modes
+        );
+        return Err(RecordError::ConfigOutOfBounds.into());
+    }
+    Ok(())
+}
+
+fn check_override_bounds(
+    overrides: &WeightingConfigOverride,
+    bounds: Option<&BoundsData>,
+) -> ProgramResult {
+    let Some(bounds) = bounds else {
+        return Ok(());
+    };
+    let weighting_mode = overrides.config.weighting_mode;
+    if overrides.overrides(WeightingConfigOverride::WEIGHTING_MODE)
+        && !bounds.allows_mode(weighting_mode)
+    {
+        msg!(
+            "Override of mode {} outside bounds of modes {:#04b}",
+            weighting_mode,
+            bounds.allowed_modes
+        );
+        return Err(RecordError::ConfigOutOfBounds.into());
+    }
+    let tc_ms = u64::from(overrides.config.tc_ms);
+    if overrides.overrides(WeightingConfigOverride::TC_MS)
+        && !(u64::from(bounds.min_tc_ms)..=u64::from(bounds.max_tc_ms)).contains(&tc_ms)
+    {
+        msg!(
+            "Override of time constant {} ms outside bounds of {}..={} ms",
+            tc_ms,
+            u64::from(bounds.min_tc_ms),
+            u64::from(bounds.max_tc_ms)
+        );
+        return Err(RecordError::ConfigOutOfBounds.into());
+    }
+    Ok(())
+}
+
+/// Get the bounds referenced by a record, from the account with their key
+/// among the accounts following the ones the instruction expects
+fn find_bounds(
+    account_data: &RecordData,
+    account_infos: &[AccountInfo],
+    program_id: &Pubkey,
+) -> Result<Option<BoundsData>, ProgramError> {
+    let Some(bounds) = account_data.bounds() else {
+        return Ok(None);
+    };
+    load_bounds(bounds, account_infos, program_id).map(Some)
+}
+
+/// Read the bounds account with the given address from the additional
+/// accounts of an instruction
+fn load_bounds(
+    bounds: &Pubkey,
+    account_infos: &[AccountInfo],
+    program_id: &Pubkey,
+) -> Result<BoundsData, ProgramError> {
+    let Some(bounds_info) = account_infos.iter().find(|info| info.key == bounds) else {
+        msg!("Bounds account {} not provided", bounds);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if bounds_info.owner != program_id {
+        msg!("Bounds account not owned by the program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let bounds_data = bounds_info.try_borrow_data()?;
+    BoundsData::unpack(&bounds_data)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn weighting_config_state_mut(
+    payload: &mut [u8],
+) -> Result<&mut WeightingConfigState, ProgramError> {
+    let state_bytes = payload
+        .get_mut(..std::mem::size_of::<WeightingConfigState>())
+        .ok_or(ProgramError::AccountDataTooSmall)?;
+    bytemuck::try_from_bytes_mut(state_bytes).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Get the history entries following the weighting config state in the
+/// writable data of a record
+fn history_entries_mut<'a>(
+    account_data: &RecordData,
+    payload: &'a mut [u8],
+) -> Result<&'a mut [HistoryEntry], ProgramError> {
+    let start = RecordData::HISTORY_START_INDEX.saturating_sub(RecordData::WRITABLE_START_INDEX);
+    let end = account_data
+        .history_end_index()
+        .saturating_sub(RecordData::WRITABLE_START_INDEX);
+    payload
+        .get_mut(start..end)
+        .and_then(|bytes| bytemuck::try_cast_slice_mut(bytes).ok())
+        .ok_or(ProgramError::AccountDataTooSmall)
+}
+
+/// Get the identity list following the history entries in the writable data
+/// of a record
+fn identity_list_mut<'a>(
+    account_data: &RecordData,
+    payload: &'a mut [u8],
+) -> Result<&'a mut [Pubkey], ProgramError> {
+    let start = account_data
+        .list_start_index()
+        .saturating_sub(RecordData::WRITABLE_START_INDEX);
+    let end = account_data
+        .list_end_index()
+        .saturating_sub(RecordData::WRITABLE_START_INDEX);
+    payload
+        .get_mut(start..end)
+        .and_then(|bytes| bytemuck::try_cast_slice_mut(bytes).ok())
+        .ok_or(ProgramError::AccountDataTooSmall)
+}
+
+/// Advance the sequence number of a record being changed
+fn bump_sequence(account_data: &mut RecordData) -> ProgramResult {
+    account_data.sequence = u64::from(account_data.sequence)
+        .checked_add(1)
+        .ok_or(RecordError::Overflow)?
+        .into();
+    Ok(())
+}
+
+/// Log an event for indexers, made of its header followed by the given fields
+fn log_event(
+    event_type: EventType,
+    record: &Pubkey,
+    signer: &Pubkey,
+    fields: &[&[u8]],
+) -> ProgramResult {
+    let header = EventHeader {
+        event_type: event_type as u8,
+        record: *record,
+        signer: *signer,
+        slot: Clock::get()?.slot.into(),
+    };
+    let mut data = Vec::with_capacity(fields.len().saturating_add(1));
+    data.push(bytemuck::bytes_of(&header));
+    data.extend_from_slice(fields);
+    log_data(&data);
+    Ok(())
+}
+
+/// Log a write to a record with the bytes it replaced
+fn log_write_event(
+    record: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    old_data: &[u8],
+    new_data: &[u8],
+) -> ProgramResult {
+    let event = WriteEvent {
+        offset: offset.into(),
+    };
+    log_event(
+        EventType::Write,
+        record,
+        signer,
+        &[bytemuck::bytes_of(&event), old_data, new_data],
+    )
+}
+
+/// Log a change of the authority of a record
+fn log_authority_change_event(
+    record: &Pubkey,
+    signer: &Pubkey,
+    old_authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> ProgramResult {
+    let event = AuthorityChangeEvent {
+        old_authority: *old_authority,
+        new_authority: *new_authority,
+    };
+    log_event(
+        EventType::AuthorityChange,
+        record,
+        signer,
+        &[bytemuck::bytes_of(&event)],
+    )
+}
+
+/// Log a change of the signers that approve for the authority of a record
+fn log_signers_change_event(
+    record: &Pubkey,
+    signer: &Pubkey,
+    account_data: &RecordData,
+    new_threshold: u8,
+    new_signers: &[Pubkey],
+) -> ProgramResult {
+    let event = SignersChangeEvent {
+        authority: account_data.authority,
+        old_threshold: account_data.threshold,
+        new_threshold,
+    };
+    log_event(
+        EventType::SignersChange,
+        record,
+        signer,
+        &[
+            bytemuck::bytes_of(&event),
+            bytemuck::cast_slice(account_data.signers()),
+            bytemuck::cast_slice(new_signers),
+        ],
+    )
+}
+
+/// Log a change of the weighting config state, which starts the writable data
+/// of a record
+fn log_config_state_event(
+    record: &Pubkey,
+    signer: &Pubkey,
+    old_state: &WeightingConfigState,
+    new_state: &WeightingConfigState,
+) -> ProgramResult {
+    log_write_event(
+        record,
+        signer,
+        0,
+        bytemuck::bytes_of(old_state),
+        bytemuck::bytes_of(new_state),
+    )
+}
+
+/// Log a resize of a record
+fn log_reallocate_event(
+    record: &Pubkey,
+    signer: &Pubkey,
+    old_len: usize,
+    new_len: usize,
+) -> ProgramResult {
+    let event = ReallocateEvent {
+        old_len: (old_len as u64).into(),
+        new_len: (new_len as u64).into(),
+    };
+    log_event(
+        EventType::Reallocate,
+        record,
+        signer,
+        &[bytemuck::bytes_of(&event)],
+    )
+}
+
+/// Check that enough slots passed since the last change to the writable data
+/// of a record, and note the current slot as the last change
+fn check_write_interval(account_data: &mut RecordData) -> ProgramResult {
+    let slot = Clock::get()?.slot;
+    if slot < account_data.next_write_slot() {
+        msg!(
+            "Record written in slot {}, next change allowed in slot {}",
+            u64::from(account_data.last_write_slot),
+            account_data.next_write_slot()
+        );
+        return Err(RecordError::WriteTooSoon.into());
+    }
+    account_data.last_write_slot = slot.into();
+    Ok(())
+}
+
+/// Write bytes into the writable data of a record, only accepting writes to
+/// typed records that leave a valid config within the bounds and never
+/// touching the history or identity list
+fn write_record_data(
+    raw_data: &mut [u8],
+    record: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    data: &[u8],
+    bounds: Option<&BoundsData>,
+    may_schedule: bool,
+) -> ProgramResult {
+    let (account_data, payload) = unpack_record_mut(raw_data)?;
+    check_write_interval(account_data)?;
+    bump_sequence(account_data)?;
+    let start = offset as usize;
+    let end = start.saturating_add(data.len());
+    if end > account_data.writable_len(payload.len()) {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let old_state = match account_data.schema() {
+        Some(RecordSchema::WeightingConfig) => Some(*weighting_config_state_mut(payload)?),
+        _ => None,
+    };
+    log_write_event(record, signer, offset, &payload[start..end], data)?;
+    payload[start..end].copy_from_slice(data);
+
+    // typed records only accept raw writes that leave a valid config, and
+    // only the record authority schedules changes
+    match account_data.schema() {
+        Some(RecordSchema::Raw) => Ok(()),
+        Some(RecordSchema::WeightingConfig) => {
+            let state = weighting_config_state_mut(payload)?;
+            if !may_schedule
+                && old_state.is_some_and(|old_state| {
+                    old_state.pending != state.pending
+                        || old_state.activation_epoch != state.activation_epoch
+                })
+            {
+                msg!("Only the record authority can schedule a config change");
+                return Err(RecordError::IncorrectAuthority.into());
+            }
+            check_weighting_config_state(state, bounds)
+        }
+        None => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Get a copy of the header of a record still at version 1, if it is one
+fn unpack_v1_record(account_info: &AccountInfo) -> Option<RecordDataV1> {
+    RecordDataV1::unpack(&account_info.data.borrow()).copied()
+}
+
+/// Write bytes into the data of a record still at version 1, after its
+/// shorter header
+fn write_v1_record_data(
+    raw_data: &mut [u8],
+    record: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    data: &[u8],
+) -> ProgramResult {
+    let start = RecordDataV1::WRITABLE_START_INDEX.saturating_add(offset as usize);
+    let end = start.saturating_add(data.len());
+    let old_data = raw_data
+        .get_mut(start..end)
+        .ok_or(ProgramError::AccountDataTooSmall)?;
+    log_write_event(record, signer, offset, old_data, data)?;
+    old_data.copy_from_slice(data);
+    Ok(())
+}
+
+/// Record the weighting config state of a record in its history, if it keeps
+/// one
+fn record_history(raw_data: &mut [u8], signer: &Pubkey) -> ProgramResult {
+    let (account_data, payload) = unpack_record_mut(raw_data)?;
+    if !account_data.has_history() {
+        return Ok(());
+    }
+    let state = *weighting_config_state_mut(payload)?;
+    let index = account_data.history_next_index();
+    history_entries_mut(account_data, payload)?[index] = HistoryEntry {
+        slot: Clock::get()?.slot.into(),
+        signer: *signer,
+        state,
+    };
+    account_data.history_count = u64::from(account_data.history_count)
+        .checked_add(1)
+        .ok_or(RecordError::Overflow)?
+        .into();
+    Ok(())
+}
+
+/// Move all lamports out of a program account, wipe its data and hand it back
+/// to the system program, so that refunding it later in the same transaction
+/// cannot revive its old contents
+fn close_program_account(
+    account_info: &AccountInfo,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    let destination_starting_lamports = destination_info.lamports();
+    let account_lamports = account_info.lamports();
+    **account_info.lamports.borrow_mut() = 0;
+    **destination_info.lamports.borrow_mut() = destination_starting_lamports
+        .checked_add(account_lamports)
+        .ok_or(RecordError::Overflow)?;
+
+    account_info.data.borrow_mut().fill(0);
+    account_info.resize(0)?;
+    account_info.assign(&solana_system_interface::program::id());
+    Ok(())
+}
+
+/// Close a record account, logging the lamports it hands to the destination
+fn close_record_account(
+    account_info: &AccountInfo,
+    signer: &Pubkey,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    let event = CloseEvent {
+        destination: *destination_info.key,
+        lamports: account_info.lamports().into(),
+    };
+    log_event(
+        EventType::Close,
+        account_info.key,
+        signer,
+        &[bytemuck::bytes_of(&event)],
+    )?;
+    close_program_account(account_info, destination_info)
+}
+
+/// Top up the lamports of a resized account from the payer so that it stays
+/// rent exempt
+fn fund_rent_exemption<'a>(
+    account_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(account_info.data_len()).max(1);
+    let missing_lamports = rent_exempt_lamports.saturating_sub(account_info.lamports());
+    if missing_lamports == 0 {
+        return Ok(());
+    }
+    invoke(
+        &system_instruction::transfer(payer_info.key, account_info.key, missing_lamports),
+        &[
+            payer_info.clone(),
+            account_info.clone(),
+            system_program_info.clone(),
+        ],
+    )
+}
+
+/// Move the lamports a shrunk account holds beyond its rent exemption to the
+/// receiver
+fn refund_excess_rent(account_info: &AccountInfo, receiver_info: &AccountInfo) -> ProgramResult {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(account_info.data_len()).max(1);
+    let excess_lamports = account_info.lamports().saturating_sub(rent_exempt_lamports);
+    if excess_lamports == 0 {
+        return Ok(());
+    }
+    **account_info.lamports.borrow_mut() = rent_exempt_lamports;
+    **receiver_info.lamports.borrow_mut() = receiver_info
+        .lamports()
+        .checked_add(excess_lamports)
+        .ok_or(RecordError::Overflow)?;
+    Ok(())
+}
+
+/// Create a program account at a program derived address, also when the
+/// address was funded in advance
+fn create_pda_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let required_lamports = Rent::get()?.minimum_balance(space).max(1);
+    if account_info.lamports() > 0 {
+        let missing_lamports = required_lamports.saturating_sub(account_info.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, account_info.key, missing_lamports),
+                &[
+                    payer_info.clone(),
+                    account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(account_info.key, space as u64),
+            &[account_info.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account_info.key, owner),
+            &[account_info.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        )
+    } else {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                account_info.key,
+                required_lamports,
+                space as u64,
+                owner,
+            ),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[signer_seeds],
+        )
+    }
+}
+
+/// Split initialized record account data into its header and writable data
+fn unpack_record_mut(raw_data: &mut [u8]) -> Result<(&mut RecordData, &mut [u8]), ProgramError> {
+    if raw_data.len() < RecordData::WRITABLE_START_INDEX {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (header, payload) = raw_data.split_at_mut(RecordData::WRITABLE_START_INDEX);
+    let account_data = bytemuck::try_from_bytes_mut::<RecordData>(header)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    if !account_data.is_initialized() {
+        msg!("Record account not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok((account_data, payload))
+}
+
+/// Decode initialized record account data
+fn unpack_record(raw_data: &[u8]) -> Result<&RecordData, ProgramError> {
+    let account_data = raw_data
+        .get(..RecordData::WRITABLE_START_INDEX)
+        .ok_or(ProgramError::InvalidAccountData)
+        .and_then(|header| {
+            bytemuck::try_from_bytes::<RecordData>(header)
+                .map_err(|_| ProgramError::InvalidArgument)
+        })?;
+    if !account_data.is_initialized() {
+        msg!("Record account not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(account_data)
+}
+
+/// Decode the multiplier table of a record into a copy of its header and
+/// entries
+fn unpack_multiplier_table(
+    raw_data: &[u8],
+    record_key: &Pubkey,
+) -> Result<(MultiplierTableData, Vec<MultiplierEntry>), ProgramError> {
+    let (header, entries) = MultiplierTableData::unpack(raw_data).ok_or_else(|| {
+        msg!("Multiplier table not initialized");
+        ProgramError::UninitializedAccount
+    })?;
+    if header.config != *record_key {
+        msg!("Multiplier table is for record {}", header.config);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok((*header, entries.to_vec()))
+}
+
+/// Store the entries of a multiplier table, into account data resized to
+/// hold exactly them
+fn store_multiplier_entries(raw_data: &mut [u8], entries: &[MultiplierEntry]) -> ProgramResult {
+    let (header, entries_data) = raw_data.split_at_mut(MultiplierTableData::ENTRIES_START_INDEX);
+    let header = bytemuck::try_from_bytes_mut::<MultiplierTableData>(header)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    header.num_entries = (entries.len() as u32).into();
+    entries_data.copy_from_slice(bytemuck::cast_slice(entries));
+    Ok(())
+}
+
+/// Split the data of the staging account of a record into its header and the
+/// staged data
+fn unpack_staging_mut<'a>(
+    raw_data: &'a mut [u8],
+    record_key: &Pubkey,
+) -> Result<(&'a mut StagingData, &'a mut [u8]), ProgramError> {
+    if raw_data.len() < StagingData::DATA_START_INDEX {
+        msg!("Staging account not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let (header, staged_data) = raw_data.split_at_mut(StagingData::DATA_START_INDEX);
+    let staging_data = bytemuck::try_from_bytes_mut::<StagingData>(header)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if !staging_data.is_initialized() {
+        msg!("Staging account not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if staging_data.record != *record_key {
+        msg!("Staging account is for record {}", staging_data.record);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok((staging_data, staged_data))
+}
+
+/// Split initialized proposal account data into its header and the bytes to
+/// write
+fn unpack_proposal_mut(raw_data: &mut [u8]) -> Result<(&mut ProposalData, &[u8]), ProgramError> {
+    if raw_data.len() < ProposalData::DATA_START_INDEX {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (header, data) = raw_data.split_at_mut(ProposalData::DATA_START_INDEX);
+    let proposal = bytemuck::try_from_bytes_mut::<ProposalData>(header)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    if !proposal.is_initialized() {
+        msg!("Proposal account not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok((proposal, data))
+}
+
+fn check_proposal_open(proposal: &ProposalData, record_info: &AccountInfo) -> ProgramResult {
+    if proposal.record != *record_info.key {
+        msg!("Proposal is for record {}", proposal.record);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if proposal.executed != 0 {
+        msg!("Proposal already executed");
+        return Err(RecordError::ProposalAlreadyExecuted.into());
+    }
+    if proposal.is_expired(Clock::get()?.slot) {
+        msg!("Proposal expired");
+        return Err(RecordError::ProposalExpired.into());
+    }
+    Ok(())
+}
+
+/// Recompute the content hash of a program account if it is an initialized
+/// record
+fn update_content_hash(account_info: &AccountInfo) -> ProgramResult {
+    let raw_data = &mut account_info.data.borrow_mut();
+    if RecordData::unpack(raw_data).is_none_or(|account_data| !account_data.is_initialized()) {
+        return Ok(());
+    }
+    let (account_data, payload) = unpack_record_mut(raw_data)?;
+    account_data.content_hash = RecordData::compute_content_hash(payload);
+    Ok(())
+}
+
 /// Instruction processor
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
     let instruction = RecordInstruction::unpack(input)?;
+    process_record_instruction(program_id, accounts, instruction)?;
+
+    // keep the content hash of every record the instruction could have
+    // changed current, for readers to reject torn data
+    for account_info in accounts
+        .iter()
+        .filter(|account_info| account_info.is_writable && account_info.owner == program_id)
+    {
+        update_content_hash(account_info)?;
+    }
+    Ok(())
+}
+
+fn process_record_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction: RecordInstruction,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     match instruction {
@@ -46,21 +856,40 @@ pub fn process_instruction(
                 &mut raw_data[..RecordData::WRITABLE_START_INDEX],
             )
             .map_err(|_| ProgramError::InvalidArgument)?;
-            if account_data.is_initialized() {
+            if account_data.version != RecordData::UNINITIALIZED_VERSION {
                 msg!("Record account already initialized");
                 return Err(ProgramError::AccountAlreadyInitialized);
             }
 
             account_data.authority = *authority_info.key;
             account_data.version = RecordData::CURRENT_VERSION;
-            Ok(())
+            account_data.complete = 1;
+            let event = InitializeEvent {
+                authority: *authority_info.key,
+            };
+            log_event(
+                EventType::Initialize,
+                data_info.key,
+                &Pubkey::default(),
+                &[bytemuck::bytes_of(&event)],
+            )
         }
 
         RecordInstruction::Write { offset, data } => {
             msg!("RecordInstruction::Write");
             let data_info = next_account_info(account_info_iter)?;
             let authority_info = next_account_info(account_info_iter)?;
-            {
+            if let Some(v1_data) = unpack_v1_record(data_info) {
+                check_authority(authority_info, &v1_data.authority)?;
+                return write_v1_record_data(
+                    &mut data_info.data.borrow_mut(),
+                    data_info.key,
+                    authority_info.key,
+                    offset,
+                    data,
+                );
+            }
+            let (bounds, authority_approved) = {
                 let raw_data = &data_info.data.borrow();
                 if raw_data.len() < RecordData::WRITABLE_START_INDEX {
                     return Err(ProgramError::InvalidAccountData);
@@ -73,16 +902,28 @@ pub fn process_instruction(
                     msg!("Record account not initialized");
                     return Err(ProgramError::UninitializedAccount);
                 }
-                check_authority(authority_info, &account_data.authority)?;
-            }
-            let start = RecordData::WRITABLE_START_INDEX.saturating_add(offset as usize);
-            let end = start.saturating_add(data.len());
-            if end > data_info.data.borrow().len() {
-                Err(ProgramError::AccountDataTooSmall)
-            } else {
-                data_info.data.borrow_mut()[start..end].copy_from_slice(data);
-                Ok(())
-            }
+                let authority_approved = check_writer_authority(
+                    account_data,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+                check_not_frozen(account_data)?;
+                (
+                    find_bounds(account_data, account_info_iter.as_slice(), program_id)?,
+                    authority_approved,
+                )
+            };
+            let raw_data = &mut data_info.data.borrow_mut();
+            write_record_data(
+                raw_data,
+                data_info.key,
+                authority_info.key,
+                offset,
+                data,
+                bounds.as_ref(),
+                authority_approved,
+            )?;
+            record_history(raw_data, authority_info.key)
         }
 
         RecordInstruction::SetAuthority => {
@@ -91,6 +932,21 @@ pub fn process_instruction(
             let authority_info = next_account_info(account_info_iter)?;
             let new_authority_info = next_account_info(account_info_iter)?;
             let raw_data = &mut data_info.data.borrow_mut();
+            if let Some(v1_data) = RecordDataV1::unpack(raw_data).copied() {
+                check_authority(authority_info, &v1_data.authority)?;
+                let old_authority = v1_data.authority;
+                let v1_data = bytemuck::try_from_bytes_mut::<RecordDataV1>(
+                    &mut raw_data[..RecordDataV1::WRITABLE_START_INDEX],
+                )
+                .map_err(|_| ProgramError::InvalidArgument)?;
+                v1_data.authority = *new_authority_info.key;
+                return log_authority_change_event(
+                    data_info.key,
+                    authority_info.key,
+                    &old_authority,
+                    new_authority_info.key,
+                );
+            }
             if raw_data.len() < RecordData::WRITABLE_START_INDEX {
                 return Err(ProgramError::InvalidAccountData);
             }
@@ -102,9 +958,17 @@ pub fn process_instruction(
                 msg!("Record account not initialized");
                 return Err(ProgramError::UninitializedAccount);
             }
-            check_authority(authority_info, &account_data.authority)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            log_authority_change_event(
+                data_info.key,
+                authority_info.key,
+                &account_data.authority,
+                new_authority_info.key,
+            )?;
             account_data.authority = *new_authority_info.key;
-            Ok(())
+            account_data.pending_authority = Pubkey::default();
+            bump_sequence(account_data)
         }
 
         RecordInstruction::CloseAccount => {
@@ -112,34 +976,38 @@ pub fn process_instruction(
             let data_info = next_account_info(account_info_iter)?;
             let authority_info = next_account_info(account_info_iter)?;
             let destination_info = next_account_info(account_info_iter)?;
-            let raw_data = &mut data_info.data.borrow_mut();
-            if raw_data.len() < RecordData::WRITABLE_START_INDEX {
-                return Err(ProgramError::InvalidAccountData);
+            if let Some(v1_data) = unpack_v1_record(data_info) {
+                check_authority(authority_info, &v1_data.authority)?;
+                return close_record_account(data_info, authority_info.key, destination_info);
             }
-            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
-                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
-            )
-            .map_err(|_| ProgramError::InvalidArgument)?;
-            if !account_data.is_initialized() {
-                msg!("Record not initialized");
-                return Err(ProgramError::UninitializedAccount);
+            {
+                let raw_data = &mut data_info.data.borrow_mut();
+                if raw_data.len() < RecordData::WRITABLE_START_INDEX {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
+                    &mut raw_data[..RecordData::WRITABLE_START_INDEX],
+                )
+                .map_err(|_| ProgramError::InvalidArgument)?;
+                if !account_data.is_initialized() {
+                    msg!("Record not initialized");
+                    return Err(ProgramError::UninitializedAccount);
+                }
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
             }
-            check_authority(authority_info, &account_data.authority)?;
-            let destination_starting_lamports = destination_info.lamports();
-            let data_lamports = data_info.lamports();
-            **data_info.lamports.borrow_mut() = 0;
-            **destination_info.lamports.borrow_mut() = destination_starting_lamports
-                .checked_add(data_lamports)
-                .ok_or(RecordError::Overflow)?;
-            Ok(())
+            close_record_account(data_info, authority_info.key, destination_info)
         }
 
         RecordInstruction::Reallocate { data_length } => {
             msg!("RecordInstruction::Reallocate");
             let data_info = next_account_info(account_info_iter)?;
             let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
 
-            {
+            let min_account_length = {
                 let raw_data = &mut data_info.data.borrow_mut();
                 if raw_data.len() < RecordData::WRITABLE_START_INDEX {
                     return Err(ProgramError::InvalidAccountData);
@@ -152,8 +1020,16 @@ pub fn process_instruction(
                     msg!("Record not initialized");
                     return Err(ProgramError::UninitializedAccount);
                 }
-                check_authority(authority_info, &account_data.authority)?;
-            }
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
+
+                // typed records keep room for their config state, history
+                // and identity list
+                match account_data.schema() {
+                    Some(RecordSchema::WeightingConfig) => account_data.list_end_index(),
+                    _ => RecordData::WRITABLE_START_INDEX,
+                }
+            };
 
             // needed account length is the sum of the meta data length and the specified
             // data length
@@ -162,20 +1038,1374 @@ pub fn process_instruction(
                     usize::try_from(data_length).map_err(|_| ProgramError::InvalidArgument)?,
                 )
                 .unwrap();
+            if needed_account_length < min_account_length {
+                msg!(
+                    "Record account must keep at least {} bytes",
+                    min_account_length
+                );
+                return Err(ProgramError::InvalidRealloc);
+            }
 
             // reallocate
-            if data_info.data_len() >= needed_account_length {
-                msg!("no additional reallocation needed");
+            let current_account_length = data_info.data_len();
+            if current_account_length == needed_account_length {
+                msg!("no reallocation needed");
                 return Ok(());
             }
             msg!(
-                "reallocating +{:?} bytes",
-                needed_account_length
-                    .checked_sub(data_info.data_len())
-                    .unwrap(),
+                "reallocating from {} to {} bytes",
+                current_account_length,
+                needed_account_length,
             );
             data_info.resize(needed_account_length)?;
-            Ok(())
+
+            // keep the account rent exempt, without holding on to more than
+            // it needs after shrinking
+            fund_rent_exemption(data_info, payer_info, system_program_info)?;
+            if needed_account_length < current_account_length {
+                refund_excess_rent(data_info, receiver_info)?;
+            }
+            log_reallocate_event(
+                data_info.key,
+                authority_info.key,
+                current_account_length,
+                needed_account_length,
+            )?;
+            bump_sequence(unpack_record_mut(&mut data_info.data.borrow_mut())?.0)
+        }
+
+        RecordInstruction::SetWeightingConfig { config } => {
+            msg!("RecordInstruction::SetWeightingConfig");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            check_writer_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            check_weighting_config(&config)?;
+            check_config_bounds(
+                &config,
+                find_bounds(account_data, account_info_iter.as_slice(), program_id)?.as_ref(),
+            )?;
+            check_write_interval(account_data)?;
+
+            let state = weighting_config_state_mut(payload)?;
+            let new_state = WeightingConfigState::new(config);
+            log_config_state_event(data_info.key, authority_info.key, state, &new_state)?;
+            *state = new_state;
+            account_data.schema = RecordSchema::WeightingConfig as u8;
+            bump_sequence(account_data)?;
+            record_history(raw_data, authority_info.key)
+        }
+
+        RecordInstruction::ScheduleWeightingConfig {
+            config,
+            activation_epoch,
+        } => {
+            msg!("RecordInstruction::ScheduleWeightingConfig");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                msg!("Record does not hold a weighting config");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            check_weighting_config(&config)?;
+            check_config_bounds(
+                &config,
+                find_bounds(account_data, account_info_iter.as_slice(), program_id)?.as_ref(),
+            )?;
+
+            let current_epoch = Clock::get()?.epoch;
+            if activation_epoch <= current_epoch {
+                msg!(
+                    "Activation epoch {} must be after the current epoch {}",
+                    activation_epoch,
+                    current_epoch
+                );
+                return Err(RecordError::InvalidActivationEpoch.into());
+            }
+            check_write_interval(account_data)?;
+
+            // a previously scheduled change that already took effect must not be
+            // lost when it gets replaced
+            bump_sequence(account_data)?;
+            let state = weighting_config_state_mut(payload)?;
+            let mut new_state = *state;
+            new_state.activate_pending(current_epoch);
+            new_state.pending = config;
+            new_state.activation_epoch = activation_epoch.into();
+            log_config_state_event(data_info.key, authority_info.key, state, &new_state)?;
+            *state = new_state;
+            record_history(raw_data, authority_info.key)
+        }
+
+        RecordInstruction::ProposeAuthority => {
+            msg!("RecordInstruction::ProposeAuthority");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let new_authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            account_data.pending_authority = *new_authority_info.key;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::AcceptAuthority => {
+            msg!("RecordInstruction::AcceptAuthority");
+            let data_info = next_account_info(account_info_iter)?;
+            let new_authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_not_frozen(account_data)?;
+            let pending_authority = *account_data.pending_authority().ok_or_else(|| {
+                msg!("No authority transfer in progress");
+                RecordError::NoPendingAuthority
+            })?;
+            check_authority(new_authority_info, &pending_authority)?;
+            log_authority_change_event(
+                data_info.key,
+                new_authority_info.key,
+                &account_data.authority,
+                &pending_authority,
+            )?;
+            account_data.authority = pending_authority;
+            account_data.pending_authority = Pubkey::default();
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::CancelAuthorityTransfer => {
+            msg!("RecordInstruction::CancelAuthorityTransfer");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            account_data.pending_authority = Pubkey::default();
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::SetSigners { threshold, signers } => {
+            msg!("RecordInstruction::SetSigners");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            check_signer_set(signers, threshold)?;
+            log_signers_change_event(
+                data_info.key,
+                authority_info.key,
+                account_data,
+                threshold,
+                signers,
+            )?;
+
+            account_data.signers = [Pubkey::default(); RecordData::MAX_SIGNERS];
+            account_data.signers[..signers.len()].copy_from_slice(signers);
+            account_data.num_signers = signers.len() as u8;
+            account_data.threshold = threshold;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::CreateProposal {
+            expiry_slot,
+            offset,
+            data,
+        } => {
+            msg!("RecordInstruction::CreateProposal");
+            let proposal_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let proposer_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                if !record_data.approvers().contains(proposer_info.key) {
+                    msg!("Proposer does not approve changes to the record");
+                    return Err(RecordError::IncorrectAuthority.into());
+                }
+            }
+            if !proposer_info.is_signer {
+                msg!("Proposer signature missing");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let raw_data = &mut proposal_info.data.borrow_mut();
+            if raw_data.len() != ProposalData::DATA_START_INDEX.saturating_add(data.len()) {
+                msg!("Proposal account must hold exactly the proposed bytes");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let (header, proposal_data) = raw_data.split_at_mut(ProposalData::DATA_START_INDEX);
+            let proposal = bytemuck::try_from_bytes_mut::<ProposalData>(header)
+                .map_err(|_| ProgramError::InvalidArgument)?;
+            if proposal.account_type != AccountType::Uninitialized as u8 {
+                msg!("Proposal account already initialized");
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            if Clock::get()?.slot >= expiry_slot {
+                msg!("Proposal expiry slot {} already passed", expiry_slot);
+                return Err(RecordError::ProposalExpired.into());
+            }
+
+            proposal.account_type = AccountType::Proposal as u8;
+            proposal.record = *record_info.key;
+            proposal.proposer = *proposer_info.key;
+            proposal.expiry_slot = expiry_slot.into();
+            proposal.offset = offset.into();
+            proposal.approvals[0] = *proposer_info.key;
+            proposal.num_approvals = 1;
+            proposal_data.copy_from_slice(data);
+            Ok(())
+        }
+
+        RecordInstruction::ApproveProposal => {
+            msg!("RecordInstruction::ApproveProposal");
+            let proposal_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let approver_info = next_account_info(account_info_iter)?;
+            let record_data = *unpack_record(&record_info.data.borrow())?;
+            let raw_data = &mut proposal_info.data.borrow_mut();
+            let (proposal, _) = unpack_proposal_mut(raw_data)?;
+            check_proposal_open(proposal, record_info)?;
+            if !record_data.approvers().contains(approver_info.key) {
+                msg!("Approver does not approve changes to the record");
+                return Err(RecordError::IncorrectAuthority.into());
+            }
+            if !approver_info.is_signer {
+                msg!("Approver signature missing");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if proposal.approvals().contains(approver_info.key) {
+                msg!("Approver already approved");
+                return Ok(());
+            }
+
+            // approvals from keys removed from the signer set make room for
+            // the current ones
+            let approvers = record_data.approvers();
+            let mut approvals = [Pubkey::default(); RecordData::MAX_SIGNERS];
+            let mut num_approvals = 0;
+            for approval in proposal
+                .approvals()
+                .iter()
+                .filter(|approval| approvers.contains(approval))
+                .chain(std::iter::once(approver_info.key))
+            {
+                approvals[num_approvals] = *approval;
+                num_approvals += 1;
+            }
+            proposal.approvals = approvals;
+            proposal.num_approvals = num_approvals as u8;
+            Ok(())
+        }
+
+        RecordInstruction::ExecuteProposal => {
+            msg!("RecordInstruction::ExecuteProposal");
+            let proposal_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let raw_proposal_data = &mut proposal_info.data.borrow_mut();
+            let (proposal, data) = unpack_proposal_mut(raw_proposal_data)?;
+            check_proposal_open(proposal, record_info)?;
+            let raw_data = &mut record_info.data.borrow_mut();
+            let bounds = {
+                let (account_data, _) = unpack_record_mut(raw_data)?;
+                check_not_frozen(account_data)?;
+                let num_approvals = proposal.num_valid_approvals(account_data);
+                if num_approvals < account_data.required_approvals() {
+                    msg!(
+                        "{} of {} required approvals provided",
+                        num_approvals,
+                        account_data.required_approvals()
+                    );
+                    return Err(RecordError::NotEnoughSigners.into());
+                }
+                find_bounds(account_data, account_info_iter.as_slice(), program_id)?
+            };
+            write_record_data(
+                raw_data,
+                record_info.key,
+                &proposal.proposer,
+                proposal.offset.into(),
+                data,
+                bounds.as_ref(),
+                true,
+            )?;
+            record_history(raw_data, &proposal.proposer)?;
+            proposal.executed = 1;
+            Ok(())
+        }
+
+        RecordInstruction::CancelProposal => {
+            msg!("RecordInstruction::CancelProposal");
+            let proposal_info = next_account_info(account_info_iter)?;
+            let proposer_info = next_account_info(account_info_iter)?;
+            let destination_info = next_account_info(account_info_iter)?;
+            {
+                let raw_data = &mut proposal_info.data.borrow_mut();
+                let (proposal, _) = unpack_proposal_mut(raw_data)?;
+                check_authority(proposer_info, &proposal.proposer)?;
+            }
+            close_program_account(proposal_info, destination_info)
+        }
+
+        RecordInstruction::SetHistoryCapacity { capacity } => {
+            msg!("RecordInstruction::SetHistoryCapacity");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let list_size = {
+                let raw_data = &data_info.data.borrow();
+                let account_data = unpack_record(raw_data)?;
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
+                if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                account_data
+                    .list_end_index()
+                    .saturating_sub(account_data.list_start_index())
+            };
+
+            let needed_account_length = RecordData::history_end_index_for(capacity)
+                .checked_add(list_size)
+                .ok_or(RecordError::Overflow)?;
+            let current_account_length = data_info.data_len();
+            if current_account_length < needed_account_length {
+                data_info.resize(needed_account_length)?;
+                fund_rent_exemption(data_info, payer_info, system_program_info)?;
+                log_reallocate_event(
+                    data_info.key,
+                    authority_info.key,
+                    current_account_length,
+                    needed_account_length,
+                )?;
+            }
+
+            let raw_data = &mut data_info.data.borrow_mut();
+            let num_kept = {
+                let (account_data, payload) = unpack_record_mut(raw_data)?;
+
+                // keep the newest changes that fit, oldest first
+                let num_kept = account_data.history_len().min(usize::from(capacity));
+                let kept_entries = {
+                    let entries = history_entries_mut(account_data, payload)?;
+                    (0..num_kept)
+                        .rev()
+                        .filter_map(|versions_back| account_data.history_index(versions_back))
+                        .map(|index| entries[index])
+                        .collect::<Vec<_>>()
+                };
+
+                // the identity list follows the history wherever it ends
+                let old_list_start = account_data
+                    .list_start_index()
+                    .saturating_sub(RecordData::WRITABLE_START_INDEX);
+                let new_list_start = RecordData::history_end_index_for(capacity)
+                    .saturating_sub(RecordData::WRITABLE_START_INDEX);
+                let old_list_end = old_list_start
+                    .checked_add(list_size)
+                    .ok_or(RecordError::Overflow)?;
+                let new_list_end = new_list_start
+                    .checked_add(list_size)
+                    .ok_or(RecordError::Overflow)?;
+                payload.copy_within(old_list_start..old_list_end, new_list_start);
+                if new_list_start < old_list_start {
+                    payload[new_list_end..old_list_end].fill(0);
+                }
+
+                account_data.history_capacity = capacity;
+                account_data.history_count = (num_kept as u64).into();
+                bump_sequence(account_data)?;
+                let entries = history_entries_mut(account_data, payload)?;
+                entries.fill(HistoryEntry::default());
+                entries[..num_kept].copy_from_slice(&kept_entries);
+                num_kept
+            };
+            if num_kept == 0 {
+                record_history(raw_data, authority_info.key)?;
+            }
+            Ok(())
+        }
+
+        RecordInstruction::Rollback { versions_back } => {
+            msg!("RecordInstruction::Rollback");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            {
+                let (account_data, payload) = unpack_record_mut(raw_data)?;
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
+                let index = account_data
+                    .history_index(usize::from(versions_back))
+                    .filter(|_| versions_back > 0)
+                    .ok_or_else(|| {
+                        msg!(
+                            "{} changes back not held in a history of {} changes",
+                            versions_back,
+                            account_data.history_len()
+                        );
+                        RecordError::VersionNotInHistory
+                    })?;
+                // a change the entry scheduled may have taken effect since
+                let mut state = history_entries_mut(account_data, payload)?[index].state;
+                state.activate_pending(Clock::get()?.epoch);
+                let config = state.active;
+                check_config_bounds(
+                    &config,
+                    find_bounds(account_data, account_info_iter.as_slice(), program_id)?.as_ref(),
+                )?;
+                check_write_interval(account_data)?;
+                let state = weighting_config_state_mut(payload)?;
+                let new_state = WeightingConfigState::new(config);
+                log_config_state_event(data_info.key, authority_info.key, state, &new_state)?;
+                *state = new_state;
+                bump_sequence(account_data)?;
+            }
+            record_history(raw_data, authority_info.key)
+        }
+
+        RecordInstruction::WriteIfSequence {
+            expected_sequence,
+            offset,
+            data,
+        } => {
+            msg!("RecordInstruction::WriteIfSequence");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (bounds, authority_approved) = {
+                let (account_data, _) = unpack_record_mut(raw_data)?;
+                let authority_approved = check_writer_authority(
+                    account_data,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+                check_not_frozen(account_data)?;
+                let sequence = u64::from(account_data.sequence);
+                if sequence != expected_sequence {
+                    msg!(
+                        "Record is at sequence {}, expected {}",
+                        sequence,
+                        expected_sequence
+                    );
+                    return Err(RecordError::StaleSequence.into());
+                }
+                (
+                    find_bounds(account_data, account_info_iter.as_slice(), program_id)?,
+                    authority_approved,
+                )
+            };
+            write_record_data(
+                raw_data,
+                data_info.key,
+                authority_info.key,
+                offset,
+                data,
+                bounds.as_ref(),
+                authority_approved,
+            )?;
+            record_history(raw_data, authority_info.key)
+        }
+
+        RecordInstruction::CreateConfig { name } => {
+            msg!("RecordInstruction::CreateConfig");
+            let config_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            if name.len() > MAX_CONFIG_NAME_LEN {
+                msg!("Config name longer than {} bytes", MAX_CONFIG_NAME_LEN);
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let (config_address, bump_seed) =
+                find_config_address_with_program_id(authority_info.key, name, program_id);
+            if config_address != *config_info.key {
+                msg!("Config account address does not match the authority and name");
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            create_pda_account(
+                payer_info,
+                config_info,
+                system_program_info,
+                RecordData::HISTORY_START_INDEX,
+                program_id,
+                &[
+                    CONFIG_SEED,
+                    authority_info.key.as_ref(),
+                    name.as_bytes(),
+                    &[bump_seed],
+                ],
+            )?;
+
+            let raw_data = &mut config_info.data.borrow_mut();
+            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
+                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
+            )
+            .map_err(|_| ProgramError::InvalidArgument)?;
+            account_data.authority = *authority_info.key;
+            account_data.version = RecordData::CURRENT_VERSION;
+            account_data.complete = 1;
+            let event = InitializeEvent {
+                authority: *authority_info.key,
+            };
+            log_event(
+                EventType::Initialize,
+                config_info.key,
+                payer_info.key,
+                &[bytemuck::bytes_of(&event)],
+            )
+        }
+
+        RecordInstruction::Freeze => {
+            msg!("RecordInstruction::Freeze");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            // a record frozen in the middle of a change would never verify
+            if !account_data.is_complete() {
+                msg!("Record data is not complete");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            account_data.frozen = 1;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::SetOverride {
+            identity,
+            overrides,
+        } => {
+            msg!("RecordInstruction::SetOverride");
+            let override_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let bounds = {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+                if record_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                find_bounds(&record_data, account_info_iter.as_slice(), program_id)?
+            };
+            check_weighting_config_override(&overrides)?;
+            check_override_bounds(&overrides, bounds.as_ref())?;
+
+            let (override_address, bump_seed) =
+                find_override_address_with_program_id(record_info.key, &identity, program_id);
+            if override_address != *override_info.key {
+                msg!("Override account address does not match the record and identity");
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if override_info.data_is_empty() {
+                create_pda_account(
+                    payer_info,
+                    override_info,
+                    system_program_info,
+                    std::mem::size_of::<OverrideData>(),
+                    program_id,
+                    &[
+                        OVERRIDE_SEED,
+                        record_info.key.as_ref(),
+                        identity.as_ref(),
+                        &[bump_seed],
+                    ],
+                )?;
+            }
+
+            let raw_data = &mut override_info.data.borrow_mut();
+            let override_data = bytemuck::try_from_bytes_mut::<OverrideData>(raw_data)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            if !override_data.is_initialized() {
+                override_data.account_type = AccountType::Override as u8;
+                override_data.config = *record_info.key;
+                override_data.identity = identity;
+            }
+            override_data.overrides = overrides;
+            Ok(())
+        }
+
+        RecordInstruction::ClearOverride => {
+            msg!("RecordInstruction::ClearOverride");
+            let override_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let destination_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+                let raw_data = &override_info.data.borrow();
+                let override_data = OverrideData::unpack(raw_data).ok_or_else(|| {
+                    msg!("Override account not initialized");
+                    ProgramError::UninitializedAccount
+                })?;
+                if override_data.config != *record_info.key {
+                    msg!("Override is for record {}", override_data.config);
+                    return Err(ProgramError::InvalidArgument);
+                }
+            }
+            close_program_account(override_info, destination_info)
+        }
+
+        RecordInstruction::AddToList { identity } => {
+            msg!("RecordInstruction::AddToList");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let (index, list_end_index) = {
+                let raw_data = &mut data_info.data.borrow_mut();
+                let (account_data, payload) = unpack_record_mut(raw_data)?;
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
+                if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let index = identity_list_mut(account_data, payload)?
+                    .binary_search(&identity)
+                    .err()
+                    .ok_or_else(|| {
+                        msg!("{} is already listed", identity);
+                        RecordError::AlreadyListed
+                    })?;
+                (index, account_data.list_end_index())
+            };
+
+            let needed_account_length = list_end_index
+                .checked_add(std::mem::size_of::<Pubkey>())
+                .ok_or(RecordError::Overflow)?;
+            let current_account_length = data_info.data_len();
+            if current_account_length < needed_account_length {
+                data_info.resize(needed_account_length)?;
+                fund_rent_exemption(data_info, payer_info, system_program_info)?;
+                log_reallocate_event(
+                    data_info.key,
+                    authority_info.key,
+                    current_account_length,
+                    needed_account_length,
+                )?;
+            }
+
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            account_data.list_len = (account_data.list_len() as u64)
+                .checked_add(1)
+                .ok_or(RecordError::Overflow)?
+                .into();
+            bump_sequence(account_data)?;
+            let list = identity_list_mut(account_data, payload)?;
+            list.copy_within(index..list.len().saturating_sub(1), index.saturating_add(1));
+            list[index] = identity;
+            Ok(())
+        }
+
+        RecordInstruction::RemoveFromList { identity } => {
+            msg!("RecordInstruction::RemoveFromList");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            let list_end_index = {
+                let raw_data = &mut data_info.data.borrow_mut();
+                let (account_data, payload) = unpack_record_mut(raw_data)?;
+                check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(account_data)?;
+                if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let list = identity_list_mut(account_data, payload)?;
+                let index = list.binary_search(&identity).map_err(|_| {
+                    msg!("{} is not listed", identity);
+                    RecordError::NotListed
+                })?;
+                list.copy_within(index.saturating_add(1).., index);
+                let last = list.len().saturating_sub(1);
+                list[last] = Pubkey::default();
+                let list_end_index = account_data.list_end_index();
+                account_data.list_len = (account_data.list_len() as u64)
+                    .checked_sub(1)
+                    .ok_or(RecordError::Overflow)?
+                    .into();
+                bump_sequence(account_data)?;
+                list_end_index
+            };
+
+            // give back the room of the removed identity if nothing follows
+            // the list
+            if data_info.data_len() == list_end_index {
+                let new_len = list_end_index.saturating_sub(std::mem::size_of::<Pubkey>());
+                data_info.resize(new_len)?;
+                refund_excess_rent(data_info, receiver_info)?;
+                log_reallocate_event(data_info.key, authority_info.key, list_end_index, new_len)?;
+            }
+            Ok(())
+        }
+
+        RecordInstruction::SetListMode { mode } => {
+            msg!("RecordInstruction::SetListMode");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                msg!("Record does not hold a weighting config");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if ListMode::from_u8(mode).is_none() {
+                msg!("Unknown list mode {}", mode);
+                return Err(ProgramError::InvalidArgument);
+            }
+            account_data.list_mode = mode;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::CreateMultiplierTable {
+            min_multiplier_bps,
+            max_multiplier_bps,
+        } => {
+            msg!("RecordInstruction::CreateMultiplierTable");
+            let table_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+                if record_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            }
+            check_multiplier_range(min_multiplier_bps, max_multiplier_bps)?;
+
+            let (table_address, bump_seed) =
+                find_multiplier_table_address_with_program_id(record_info.key, program_id);
+            if table_address != *table_info.key {
+                msg!("Multiplier table address does not match the record");
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if !table_info.data_is_empty() {
+                msg!("Multiplier table already exists");
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            create_pda_account(
+                payer_info,
+                table_info,
+                system_program_info,
+                MultiplierTableData::ENTRIES_START_INDEX,
+                program_id,
+                &[
+                    MULTIPLIER_TABLE_SEED,
+                    record_info.key.as_ref(),
+                    &[bump_seed],
+                ],
+            )?;
+
+            let raw_data = &mut table_info.data.borrow_mut();
+            let table_data = bytemuck::try_from_bytes_mut::<MultiplierTableData>(raw_data)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            table_data.account_type = AccountType::MultiplierTable as u8;
+            table_data.config = *record_info.key;
+            table_data.min_multiplier_bps = min_multiplier_bps.into();
+            table_data.max_multiplier_bps = max_multiplier_bps.into();
+            Ok(())
+        }
+
+        RecordInstruction::SetMultipliers { entries } => {
+            msg!("RecordInstruction::SetMultipliers");
+            let table_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+                if record_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            }
+            let (table_data, mut table_entries) =
+                unpack_multiplier_table(&table_info.data.borrow(), record_info.key)?;
+
+            for entry in entries {
+                let multiplier_bps = u32::from(entry.multiplier_bps);
+                if !table_data.accepts(multiplier_bps) {
+                    msg!(
+                        "Multiplier {} bps for {} out of range",
+                        multiplier_bps,
+                        entry.identity
+                    );
+                    return Err(RecordError::MultiplierOutOfRange.into());
+                }
+                match table_entries
+                    .binary_search_by(|table_entry| table_entry.identity.cmp(&entry.identity))
+                {
+                    Ok(index) => table_entries[index] = *entry,
+                    Err(index) => table_entries.insert(index, *entry),
+                }
+            }
+            if table_entries.len() > MultiplierTableData::MAX_ENTRIES {
+                msg!(
+                    "Multiplier table holds at most {} entries",
+                    MultiplierTableData::MAX_ENTRIES
+                );
+                return Err(RecordError::MultiplierTableFull.into());
+            }
+
+            table_info.resize(MultiplierTableData::entries_end_index_for(
+                table_entries.len(),
+            ))?;
+            fund_rent_exemption(table_info, payer_info, system_program_info)?;
+            store_multiplier_entries(&mut table_info.data.borrow_mut(), &table_entries)
+        }
+
+        RecordInstruction::RemoveMultipliers { identities } => {
+            msg!("RecordInstruction::RemoveMultipliers");
+            let table_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+            }
+            let (_, mut table_entries) =
+                unpack_multiplier_table(&table_info.data.borrow(), record_info.key)?;
+
+            for identity in identities {
+                let index = table_entries
+                    .binary_search_by(|table_entry| table_entry.identity.cmp(identity))
+                    .map_err(|_| {
+                        msg!("{} has no multiplier", identity);
+                        RecordError::MultiplierNotFound
+                    })?;
+                table_entries.remove(index);
+            }
+
+            table_info.resize(MultiplierTableData::entries_end_index_for(
+                table_entries.len(),
+            ))?;
+            refund_excess_rent(table_info, receiver_info)?;
+            store_multiplier_entries(&mut table_info.data.borrow_mut(), &table_entries)
+        }
+
+        RecordInstruction::SetComplete { complete } => {
+            msg!("RecordInstruction::SetComplete");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_writer_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+            account_data.complete = u8::from(complete);
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::BeginStagedWrite => {
+            msg!("RecordInstruction::BeginStagedWrite");
+            let staging_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let (base_sequence, staged_data) = {
+                let raw_data = &record_info.data.borrow();
+                let record_data = unpack_record(raw_data)?;
+                check_record_authority(record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(record_data)?;
+                let payload = &raw_data[RecordData::WRITABLE_START_INDEX..];
+                (
+                    record_data.sequence,
+                    payload[..record_data.writable_len(payload.len())].to_vec(),
+                )
+            };
+
+            let (staging_address, bump_seed) =
+                find_staging_address_with_program_id(record_info.key, program_id);
+            if staging_address != *staging_info.key {
+                msg!("Staging account address does not match the record");
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if !staging_info.data_is_empty() {
+                msg!("Staged write already in progress");
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            create_pda_account(
+                payer_info,
+                staging_info,
+                system_program_info,
+                StagingData::DATA_START_INDEX
+                    .checked_add(staged_data.len())
+                    .ok_or(RecordError::Overflow)?,
+                program_id,
+                &[STAGING_SEED, record_info.key.as_ref(), &[bump_seed]],
+            )?;
+
+            let raw_data = &mut staging_info.data.borrow_mut();
+            let (header, data) = raw_data.split_at_mut(StagingData::DATA_START_INDEX);
+            let staging_data = bytemuck::try_from_bytes_mut::<StagingData>(header)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            staging_data.account_type = AccountType::Staging as u8;
+            staging_data.record = *record_info.key;
+            staging_data.base_sequence = base_sequence;
+            data.copy_from_slice(&staged_data);
+            Ok(())
+        }
+
+        RecordInstruction::StageChunk { offset, data } => {
+            msg!("RecordInstruction::StageChunk");
+            let staging_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+            }
+            let raw_data = &mut staging_info.data.borrow_mut();
+            let (_, staged_data) = unpack_staging_mut(raw_data, record_info.key)?;
+            let start = offset as usize;
+            let end = start.saturating_add(data.len());
+            if end > staged_data.len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            staged_data[start..end].copy_from_slice(data);
+            Ok(())
+        }
+
+        RecordInstruction::CommitStagedWrite { expected_hash } => {
+            msg!("RecordInstruction::CommitStagedWrite");
+            let staging_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            {
+                let staging_raw_data = &mut staging_info.data.borrow_mut();
+                let (staging_data, staged_data) =
+                    unpack_staging_mut(staging_raw_data, record_info.key)?;
+                if RecordData::compute_content_hash(staged_data) != expected_hash {
+                    msg!("Staged data does not match the expected hash");
+                    return Err(RecordError::StagedHashMismatch.into());
+                }
+
+                let raw_data = &mut record_info.data.borrow_mut();
+                let bounds = {
+                    let record_data = unpack_record(raw_data)?;
+                    check_record_authority(
+                        record_data,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    check_not_frozen(record_data)?;
+                    if record_data.sequence != staging_data.base_sequence {
+                        msg!(
+                            "Record is at sequence {}, staging began at {}",
+                            u64::from(record_data.sequence),
+                            u64::from(staging_data.base_sequence)
+                        );
+                        return Err(RecordError::StaleSequence.into());
+                    }
+                    find_bounds(record_data, account_info_iter.as_slice(), program_id)?
+                };
+                write_record_data(
+                    raw_data,
+                    record_info.key,
+                    authority_info.key,
+                    0,
+                    staged_data,
+                    bounds.as_ref(),
+                    true,
+                )?;
+                record_history(raw_data, authority_info.key)?;
+            }
+            close_program_account(staging_info, receiver_info)
+        }
+
+        RecordInstruction::AbortStagedWrite => {
+            msg!("RecordInstruction::AbortStagedWrite");
+            let staging_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                unpack_staging_mut(&mut staging_info.data.borrow_mut(), record_info.key)?;
+            }
+            close_program_account(staging_info, receiver_info)
+        }
+
+        RecordInstruction::SetWriter { writer, allowed } => {
+            msg!("RecordInstruction::SetWriter");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+
+            let num_writers = usize::from(account_data.num_writers).min(RecordData::MAX_WRITERS);
+            let position = account_data
+                .writers()
+                .iter()
+                .position(|listed| *listed == writer);
+            match (position, allowed) {
+                (None, true) => {
+                    if num_writers == RecordData::MAX_WRITERS {
+                        msg!("Record has at most {} writers", RecordData::MAX_WRITERS);
+                        return Err(RecordError::TooManyWriters.into());
+                    }
+                    account_data.writers[num_writers] = writer;
+                    account_data.num_writers = num_writers.saturating_add(1) as u8;
+                }
+                (Some(index), false) => {
+                    account_data
+                        .writers
+                        .copy_within(index.saturating_add(1)..num_writers, index);
+                    let last = num_writers.saturating_sub(1);
+                    account_data.writers[last] = Pubkey::default();
+                    account_data.num_writers = last as u8;
+                }
+                _ => {}
+            }
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::SetEmergencyAuthority {
+            emergency_authority,
+        } => {
+            msg!("RecordInstruction::SetEmergencyAuthority");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+
+            account_data.emergency_authority = emergency_authority;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::EmergencyStatic => {
+            msg!("RecordInstruction::EmergencyStatic");
+            let data_info = next_account_info(account_info_iter)?;
+            let emergency_authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            let Some(emergency_authority) = account_data.emergency_authority() else {
+                msg!("Record has no emergency authority");
+                return Err(RecordError::IncorrectAuthority.into());
+            };
+            check_authority(emergency_authority_info, emergency_authority)?;
+            check_not_frozen(account_data)?;
+            if account_data.schema() != Some(RecordSchema::WeightingConfig) {
+                msg!("Record does not hold a weighting config");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            // force static weighting on the config in effect, which may be a
+            // scheduled change that already took effect
+            let state = weighting_config_state_mut(payload)?;
+            let mut activated_state = *state;
+            activated_state.activate_pending(Clock::get()?.epoch);
+            let mut config = activated_state.active;
+            config.weighting_mode = WeightingMode::Static as u8;
+            let new_state = WeightingConfigState::new(config);
+            log_config_state_event(
+                data_info.key,
+                emergency_authority_info.key,
+                state,
+                &new_state,
+            )?;
+            *state = new_state;
+            account_data.emergency_triggered_by = *emergency_authority_info.key;
+            // the emergency path is never held back, but still counts as a change
+            let slot = Clock::get()?.slot;
+            account_data.emergency_slot = slot.into();
+            account_data.last_write_slot = slot.into();
+            bump_sequence(account_data)?;
+            record_history(raw_data, emergency_authority_info.key)
+        }
+
+        RecordInstruction::SetMinSlotsBetweenWrites { min_slots } => {
+            msg!("RecordInstruction::SetMinSlotsBetweenWrites");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+
+            account_data.min_slots_between_writes = min_slots.into();
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::CreateBounds {
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        } => {
+            msg!("RecordInstruction::CreateBounds");
+            let bounds_info = next_account_info(account_info_iter)?;
+            let governance_authority_info = next_account_info(account_info_iter)?;
+            if !governance_authority_info.is_signer {
+                msg!("Governance authority signature missing");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            check_bounds_range(min_tc_ms, max_tc_ms, allowed_modes)?;
+
+            let raw_data = &mut bounds_info.data.borrow_mut();
+            let bounds_data = bytemuck::try_from_bytes_mut::<BoundsData>(raw_data)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            if bounds_data.account_type != AccountType::Uninitialized as u8 {
+                msg!("Bounds account already initialized");
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+
+            bounds_data.account_type = AccountType::Bounds as u8;
+            bounds_data.authority = *governance_authority_info.key;
+            bounds_data.min_tc_ms = min_tc_ms.into();
+            bounds_data.max_tc_ms = max_tc_ms.into();
+            bounds_data.allowed_modes = allowed_modes;
+            Ok(())
+        }
+
+        RecordInstruction::UpdateBounds {
+            min_tc_ms,
+            max_tc_ms,
+            allowed_modes,
+        } => {
+            msg!("RecordInstruction::UpdateBounds");
+            let bounds_info = next_account_info(account_info_iter)?;
+            let governance_authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut bounds_info.data.borrow_mut();
+            let bounds_data = bytemuck::try_from_bytes_mut::<BoundsData>(raw_data)
+                .ok()
+                .filter(|bounds_data| bounds_data.is_initialized())
+                .ok_or(ProgramError::UninitializedAccount)?;
+            check_authority(governance_authority_info, &bounds_data.authority)?;
+            check_bounds_range(min_tc_ms, max_tc_ms, allowed_modes)?;
+
+            bounds_data.min_tc_ms = min_tc_ms.into();
+            bounds_data.max_tc_ms = max_tc_ms.into();
+            bounds_data.allowed_modes = allowed_modes;
+            Ok(())
+        }
+
+        RecordInstruction::SetBounds { bounds } => {
+            msg!("RecordInstruction::SetBounds");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let raw_data = &mut data_info.data.borrow_mut();
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            check_record_authority(account_data, authority_info, account_info_iter.as_slice())?;
+            check_not_frozen(account_data)?;
+
+            // the new bounds must exist and accept the config in effect, or
+            // every later config write would fail
+            if bounds != Pubkey::default() {
+                let new_bounds = load_bounds(&bounds, account_info_iter.as_slice(), program_id)?;
+                if account_data.schema() == Some(RecordSchema::WeightingConfig) {
+                    let state = weighting_config_state_mut(payload)?;
+                    check_config_bounds(state.effective_config(&Clock::get()?), Some(&new_bounds))?;
+                }
+            }
+
+            // bounds once set are only removed or replaced with the approval
+            // of the governance authority holding them
+            if account_data
+                .bounds()
+                .is_some_and(|current| *current != bounds)
+            {
+                let current_bounds =
+                    find_bounds(account_data, account_info_iter.as_slice(), program_id)?
+                        .ok_or(ProgramError::InvalidAccountData)?;
+                if !account_info_iter
+                    .as_slice()
+                    .iter()
+                    .any(|info| info.is_signer && *info.key == current_bounds.authority)
+                {
+                    msg!("Governance authority of the current bounds must approve the change");
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+            }
+
+            account_data.bounds = bounds;
+            bump_sequence(account_data)
+        }
+
+        RecordInstruction::Migrate => {
+            msg!("RecordInstruction::Migrate");
+            let data_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let payer_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            let v1_data = unpack_v1_record(data_info).ok_or_else(|| {
+                msg!("Record is not at version {}", RecordDataV1::VERSION);
+                ProgramError::InvalidAccountData
+            })?;
+            check_authority(authority_info, &v1_data.authority)?;
+            let legacy_config = RecordDataV1::unpack_legacy_config(&data_info.data.borrow())
+                .filter(|config| check_weighting_config(config).is_ok());
+
+            // the data moves after the new header, which takes its place,
+            // unless it is a config that becomes the config state
+            let old_len = data_info.data_len();
+            let payload_len = old_len.saturating_sub(RecordDataV1::WRITABLE_START_INDEX);
+            let new_len = match legacy_config {
+                Some(_) => RecordData::HISTORY_START_INDEX,
+                None => RecordData::WRITABLE_START_INDEX.saturating_add(payload_len),
+            };
+            data_info.resize(new_len)?;
+            fund_rent_exemption(data_info, payer_info, system_program_info)?;
+            if new_len < old_len {
+                refund_excess_rent(data_info, payer_info)?;
+            }
+            log_reallocate_event(data_info.key, authority_info.key, old_len, new_len)?;
+
+            let raw_data = &mut data_info.data.borrow_mut();
+            match legacy_config {
+                Some(config) => {
+                    *weighting_config_state_mut(
+                        &mut raw_data[RecordData::WRITABLE_START_INDEX..],
+                    )? = WeightingConfigState::new(config);
+                }
+                None => raw_data.copy_within(
+                    RecordDataV1::WRITABLE_START_INDEX
+                        ..RecordDataV1::WRITABLE_START_INDEX.saturating_add(payload_len),
+                    RecordData::WRITABLE_START_INDEX,
+                ),
+            }
+            raw_data[..RecordData::WRITABLE_START_INDEX].fill(0);
+            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
+                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
+            )
+            .map_err(|_| ProgramError::InvalidArgument)?;
+            account_data.version = RecordData::CURRENT_VERSION;
+            account_data.authority = v1_data.authority;
+            account_data.complete = 1;
+            if legacy_config.is_some() {
+                account_data.schema = RecordSchema::WeightingConfig as u8;
+            }
+            Ok(())
+        }
+
+        RecordInstruction::GetEffectiveConfig { identity } => {
+            msg!("RecordInstruction::GetEffectiveConfig");
+            let record_info = next_account_info(account_info_iter)?;
+            let override_info = next_account_info(account_info_iter)?;
+            if record_info.owner != program_id {
+                msg!("Record account not owned by the program");
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let (override_address, _) =
+                find_override_address_with_program_id(record_info.key, &identity, program_id);
+            if override_address != *override_info.key {
+                msg!("Override account address does not match the record and identity");
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            // an override account that was never set is not owned by the
+            // program
+            let override_data =
+                (override_info.owner == program_id).then(|| override_info.data.borrow());
+            let config = resolve_config(
+                record_info.key,
+                &record_info.data.borrow(),
+                &identity,
+                override_data.as_ref().map(|data| &data[..]),
+                &Clock::get()?,
+            )
+            .ok_or_else(|| {
+                msg!("Record holds no complete weighting config");
+                ProgramError::InvalidAccountData
+            })?;
+            set_return_data(bytemuck::bytes_of(&config));
+            Ok(())
+        }
+
+        RecordInstruction::SetMultiplierRange {
+            min_multiplier_bps,
+            max_multiplier_bps,
+        } => {
+            msg!("RecordInstruction::SetMultiplierRange");
+            let table_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+                if record_data.schema() != Some(RecordSchema::WeightingConfig) {
+                    msg!("Record does not hold a weighting config");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            }
+            check_multiplier_range(min_multiplier_bps, max_multiplier_bps)?;
+            let (_, table_entries) =
+                unpack_multiplier_table(&table_info.data.borrow(), record_info.key)?;
+
+            let range = min_multiplier_bps..=max_multiplier_bps;
+            if let Some(entry) = table_entries
+                .iter()
+                .find(|entry| !range.contains(&u32::from(entry.multiplier_bps)))
+            {
+                msg!(
+                    "Multiplier {} bps for {} out of the new range",
+                    u32::from(entry.multiplier_bps),
+                    entry.identity
+                );
+                return Err(RecordError::MultiplierOutOfRange.into());
+            }
+
+            let raw_data = &mut table_info.data.borrow_mut();
+            let table_data = bytemuck::try_from_bytes_mut::<MultiplierTableData>(
+                &mut raw_data[..MultiplierTableData::ENTRIES_START_INDEX],
+            )
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+            table_data.min_multiplier_bps = min_multiplier_bps.into();
+            table_data.max_multiplier_bps = max_multiplier_bps.into();
+            Ok(())
+        }
+
+        RecordInstruction::CloseMultiplierTable => {
+            msg!("RecordInstruction::CloseMultiplierTable");
+            let table_info = next_account_info(account_info_iter)?;
+            let record_info = next_account_info(account_info_iter)?;
+            let authority_info = next_account_info(account_info_iter)?;
+            let receiver_info = next_account_info(account_info_iter)?;
+            {
+                let record_data = *unpack_record(&record_info.data.borrow())?;
+                check_record_authority(&record_data, authority_info, account_info_iter.as_slice())?;
+                check_not_frozen(&record_data)?;
+            }
+            unpack_multiplier_table(&table_info.data.borrow(), record_info.key)?;
+            close_program_account(table_info, receiver_info)
         }
     }
 }
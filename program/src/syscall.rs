@@ -0,0 +1,70 @@
+//! Runtime calls made by the program
+//!
+//! On chain these go to `solana-cpi` and the syscalls directly. Elsewhere they
+//! go through the syscall stubs, which `solana-program-test` replaces to run
+//! the program natively.
+
+use {
+    solana_account_info::AccountInfo, solana_instruction::Instruction,
+    solana_program_error::ProgramResult, solana_pubkey::Pubkey,
+};
+
+/// Invoke another program, see `solana_cpi::invoke`
+pub(crate) fn invoke(instruction: &Instruction, account_infos: &[AccountInfo]) -> ProgramResult {
+    invoke_signed(instruction, account_infos, &[])
+}
+
+/// Invoke another program signing for program derived addresses, see
+/// `solana_cpi::invoke_signed`
+pub(crate) fn invoke_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    #[cfg(target_os = "solana")]
+    {
+        solana_cpi::invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    solana_sysvar::program_stubs::sol_invoke_signed(instruction, account_infos, signers_seeds)
+}
+
+/// Set the return data of the running program, see
+/// `solana_cpi::set_return_data`
+pub(crate) fn set_return_data(data: &[u8]) {
+    #[cfg(target_os = "solana")]
+    {
+        solana_cpi::set_return_data(data);
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    solana_sysvar::program_stubs::sol_set_return_data(data)
+}
+
+/// Get the return data of the last invoked program, see
+/// `solana_cpi::get_return_data`
+pub(crate) fn get_return_data() -> Option<(Pubkey, Vec<u8>)> {
+    #[cfg(target_os = "solana")]
+    {
+        solana_cpi::get_return_data()
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    solana_sysvar::program_stubs::sol_get_return_data()
+}
+
+/// Log data fields, shown base64 encoded as `Program data:` in the
+/// transaction logs
+pub(crate) fn log_data(fields: &[&[u8]]) {
+    #[cfg(target_os = "solana")]
+    unsafe {
+        solana_define_syscall::definitions::sol_log_data(
+            fields as *const _ as *const u8,
+            fields.len() as u64,
+        )
+    };
+
+    #[cfg(not(target_os = "solana"))]
+    solana_sysvar::program_stubs::sol_log_data(fields)
+}
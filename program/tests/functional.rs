@@ -1,22 +1,41 @@
 #![cfg(feature = "test-sbf")]
 
 use {
+    gossip_weight_controller::{
+        cpi,
+        error::RecordError,
+        find_config_address, find_multiplier_table_address, find_override_address,
+        find_staging_address, id, instruction, is_listed,
+        processor::process_instruction,
+        state::{
+            resolve_config, BoundsData, HistoryEntry, ListMode, MultiplierEntry,
+            MultiplierTableData, OverrideData, ProposalData, RecordData, RecordDataV1,
+            RecordSchema, StagingData, WeightingConfig, WeightingConfigOverride,
+            WeightingConfigState, WeightingMode,
+        },
+    },
+    solana_account_info::AccountInfo,
+    solana_clock::Clock,
     solana_instruction::{error::InstructionError, AccountMeta, Instruction},
+    solana_program_error::{ProgramError, ProgramResult},
     solana_program_test::*,
     solana_pubkey::Pubkey,
     solana_rent::Rent,
     solana_sdk::{
+        account::Account,
+        program::set_return_data,
         signature::{Keypair, Signer},
-        system_instruction,
         transaction::{Transaction, TransactionError},
     },
-    spl_record::{
-        error::RecordError, id, instruction, processor::process_instruction, state::RecordData,
-    },
+    solana_system_interface::instruction as system_instruction,
 };
 
 fn program_test() -> ProgramTest {
-    ProgramTest::new("spl_record", id(), processor!(process_instruction))
+    ProgramTest::new(
+        "gossip_weight_controller",
+        id(),
+        processor!(process_instruction),
+    )
 }
 
 async fn initialize_storage_account(
@@ -38,7 +57,39 @@ async fn initialize_storage_account(
                 &id(),
             ),
             instruction::initialize(&account.pubkey(), &authority.pubkey()),
-            instruction::write(&account.pubkey(), &authority.pubkey(), 0, data),
+            instruction::write(&account.pubkey(), &authority.pubkey(), 0, data).unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, account, authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn initialize_weighting_config_account(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+    config: &WeightingConfig,
+) {
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(std::mem::size_of::<WeightingConfigState>())
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+                account_length as u64,
+                &id(),
+            ),
+            instruction::initialize(&account.pubkey(), &authority.pubkey()),
+            instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), config),
         ],
         Some(&context.payer.pubkey()),
         &[&context.payer, account, authority],
@@ -97,7 +148,7 @@ async fn initialize_with_seed_success() {
                 &id(),
             ),
             instruction::initialize(&account, &authority.pubkey()),
-            instruction::write(&account, &authority.pubkey(), 0, data),
+            instruction::write(&account, &authority.pubkey(), 0, data).unwrap(),
         ],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
@@ -161,12 +212,7 @@ async fn write_success() {
 
     let new_data = &[200u8; 8];
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction::write(
-            &account.pubkey(),
-            &authority.pubkey(),
-            0,
-            new_data,
-        )],
+        &[instruction::write(&account.pubkey(), &authority.pubkey(), 0, new_data).unwrap()],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
         context.last_blockhash,
@@ -203,12 +249,7 @@ async fn write_fail_wrong_authority() {
     let new_data = &[200u8; 8];
     let wrong_authority = Keypair::new();
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction::write(
-            &account.pubkey(),
-            &wrong_authority.pubkey(),
-            0,
-            new_data,
-        )],
+        &[instruction::write(&account.pubkey(), &wrong_authority.pubkey(), 0, new_data).unwrap()],
         Some(&context.payer.pubkey()),
         &[&context.payer, &wrong_authority],
         context.last_blockhash,
@@ -245,7 +286,9 @@ async fn write_fail_unsigned() {
                 AccountMeta::new(account.pubkey(), false),
                 AccountMeta::new_readonly(authority.pubkey(), false),
             ],
-            data: instruction::RecordInstruction::Write { offset: 0, data }.pack(),
+            data: instruction::RecordInstruction::Write { offset: 0, data }
+                .pack()
+                .unwrap(),
         }],
         Some(&context.payer.pubkey()),
         &[&context.payer],
@@ -303,6 +346,86 @@ async fn close_account_success() {
     );
 }
 
+#[tokio::test]
+async fn close_account_revived_account_is_wiped() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let recipient = Pubkey::new_unique();
+
+    // refund the account within the same transaction
+    let revival_lamports = Rent::default().minimum_balance(0);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::close_account(&account.pubkey(), &authority.pubkey(), &recipient),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                revival_lamports,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, solana_sdk::system_program::id());
+    assert!(account.data.is_empty());
+    assert_eq!(account.lamports, revival_lamports);
+}
+
+#[tokio::test]
+async fn close_account_fail_revived_write() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data.len())
+        .unwrap();
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let recipient = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::close_account(&account.pubkey(), &authority.pubkey(), &recipient),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+            ),
+            instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[200u8; 8]).unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(2, InstructionError::InvalidAccountData)
+    );
+}
+
 #[tokio::test]
 async fn close_account_fail_wrong_authority() {
     let mut context = program_test().start_with_context().await;
@@ -321,7 +444,7 @@ async fn close_account_fail_wrong_authority() {
                 AccountMeta::new_readonly(wrong_authority.pubkey(), true),
                 AccountMeta::new(Pubkey::new_unique(), false),
             ],
-            data: instruction::RecordInstruction::CloseAccount.pack(),
+            data: instruction::RecordInstruction::CloseAccount.pack().unwrap(),
         }],
         Some(&context.payer.pubkey()),
         &[&context.payer, &wrong_authority],
@@ -358,7 +481,7 @@ async fn close_account_fail_unsigned() {
                 AccountMeta::new_readonly(authority.pubkey(), false),
                 AccountMeta::new(Pubkey::new_unique(), false),
             ],
-            data: instruction::RecordInstruction::CloseAccount.pack(),
+            data: instruction::RecordInstruction::CloseAccount.pack().unwrap(),
         }],
         Some(&context.payer.pubkey()),
         &[&context.payer],
@@ -415,12 +538,7 @@ async fn set_authority_success() {
 
     let new_data = &[200u8; 8];
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction::write(
-            &account.pubkey(),
-            &new_authority.pubkey(),
-            0,
-            new_data,
-        )],
+        &[instruction::write(&account.pubkey(), &new_authority.pubkey(), 0, new_data).unwrap()],
         Some(&context.payer.pubkey()),
         &[&context.payer, &new_authority],
         context.last_blockhash,
@@ -467,7 +585,7 @@ async fn set_authority_fail_wrong_authority() {
                 AccountMeta::new_readonly(wrong_authority.pubkey(), true),
                 AccountMeta::new(Pubkey::new_unique(), false),
             ],
-            data: instruction::RecordInstruction::SetAuthority.pack(),
+            data: instruction::RecordInstruction::SetAuthority.pack().unwrap(),
         }],
         Some(&context.payer.pubkey()),
         &[&context.payer, &wrong_authority],
@@ -504,7 +622,7 @@ async fn set_authority_fail_unsigned() {
                 AccountMeta::new_readonly(authority.pubkey(), false),
                 AccountMeta::new(Pubkey::new_unique(), false),
             ],
-            data: instruction::RecordInstruction::SetAuthority.pack(),
+            data: instruction::RecordInstruction::SetAuthority.pack().unwrap(),
         }],
         Some(&context.payer.pubkey()),
         &[&context.payer],
@@ -529,25 +647,21 @@ async fn reallocate_success() {
     let account = Keypair::new();
     let data = &[222u8; 8];
     initialize_storage_account(&mut context, &authority, &account, data).await;
+    let receiver = Pubkey::new_unique();
 
+    // the payer tops up the rent
     let new_data_length = 16u64;
     let expected_account_data_length = RecordData::WRITABLE_START_INDEX
         .checked_add(new_data_length as usize)
         .unwrap();
-
-    let delta_account_data_length = new_data_length.saturating_sub(data.len() as u64);
-    let additional_lamports_needed =
-        Rent::default().minimum_balance(delta_account_data_length as usize);
-
     let transaction = Transaction::new_signed_with_payer(
-        &[
-            instruction::reallocate(&account.pubkey(), &authority.pubkey(), new_data_length),
-            system_instruction::transfer(
-                &context.payer.pubkey(),
-                &account.pubkey(),
-                additional_lamports_needed,
-            ),
-        ],
+        &[instruction::reallocate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &receiver,
+            new_data_length,
+        )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
         context.last_blockhash,
@@ -566,15 +680,28 @@ async fn reallocate_success() {
         .unwrap();
 
     assert_eq!(account_handle.data.len(), expected_account_data_length);
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(expected_account_data_length)
+    );
 
-    // reallocate to a smaller length
-    let old_data_length = 8u64;
+    // reallocate to a smaller length, refunding the excess rent
+    let old_data_length = 4u64;
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
+        .checked_add(old_data_length as usize)
+        .unwrap();
+    let receiver_lamports = Rent::default().minimum_balance(0);
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction::reallocate(
-            &account.pubkey(),
-            &authority.pubkey(),
-            old_data_length,
-        )],
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &receiver, receiver_lamports),
+            instruction::reallocate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &receiver,
+                old_data_length,
+            ),
+        ],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
         context.last_blockhash,
@@ -593,6 +720,56 @@ async fn reallocate_success() {
         .unwrap();
 
     assert_eq!(account.data.len(), expected_account_data_length);
+    assert_eq!(
+        &account.data[RecordData::WRITABLE_START_INDEX..],
+        &data[..4]
+    );
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(expected_account_data_length)
+    );
+    let receiver = context
+        .banks_client
+        .get_account(receiver)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        receiver.lamports,
+        receiver_lamports + account_handle.lamports - account.lamports
+    );
+}
+
+#[tokio::test]
+async fn reallocate_fail_shrink_below_config_state() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::reallocate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            8,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidRealloc)
+    );
 }
 
 #[tokio::test]
@@ -605,30 +782,24 @@ async fn reallocate_fail_wrong_authority() {
     initialize_storage_account(&mut context, &authority, &account, data).await;
 
     let new_data_length = 16u64;
-    let delta_account_data_length = new_data_length.saturating_sub(data.len() as u64);
-    let additional_lamports_needed =
-        Rent::default().minimum_balance(delta_account_data_length as usize);
 
     let wrong_authority = Keypair::new();
     let transaction = Transaction::new_signed_with_payer(
-        &[
-            Instruction {
-                program_id: id(),
-                accounts: vec![
-                    AccountMeta::new(account.pubkey(), false),
-                    AccountMeta::new(wrong_authority.pubkey(), true),
-                ],
-                data: instruction::RecordInstruction::Reallocate {
-                    data_length: new_data_length,
-                }
-                .pack(),
-            },
-            system_instruction::transfer(
-                &context.payer.pubkey(),
-                &account.pubkey(),
-                additional_lamports_needed,
-            ),
-        ],
+        &[Instruction {
+            program_id: id(),
+            accounts: vec![
+                AccountMeta::new(account.pubkey(), false),
+                AccountMeta::new(wrong_authority.pubkey(), true),
+                AccountMeta::new(context.payer.pubkey(), true),
+                AccountMeta::new(context.payer.pubkey(), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data: instruction::RecordInstruction::Reallocate {
+                data_length: new_data_length,
+            }
+            .pack()
+            .unwrap(),
+        }],
         Some(&context.payer.pubkey()),
         &[&context.payer, &wrong_authority],
         context.last_blockhash,
@@ -658,29 +829,23 @@ async fn reallocate_fail_unsigned() {
     initialize_storage_account(&mut context, &authority, &account, data).await;
 
     let new_data_length = 16u64;
-    let delta_account_data_length = new_data_length.saturating_sub(data.len() as u64);
-    let additional_lamports_needed =
-        Rent::default().minimum_balance(delta_account_data_length as usize);
 
     let transaction = Transaction::new_signed_with_payer(
-        &[
-            Instruction {
-                program_id: id(),
-                accounts: vec![
-                    AccountMeta::new(account.pubkey(), false),
-                    AccountMeta::new(authority.pubkey(), false),
-                ],
-                data: instruction::RecordInstruction::Reallocate {
-                    data_length: new_data_length,
-                }
-                .pack(),
-            },
-            system_instruction::transfer(
-                &context.payer.pubkey(),
-                &account.pubkey(),
-                additional_lamports_needed,
-            ),
-        ],
+        &[Instruction {
+            program_id: id(),
+            accounts: vec![
+                AccountMeta::new(account.pubkey(), false),
+                AccountMeta::new(authority.pubkey(), false),
+                AccountMeta::new(context.payer.pubkey(), true),
+                AccountMeta::new(context.payer.pubkey(), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data: instruction::RecordInstruction::Reallocate {
+                data_length: new_data_length,
+            }
+            .pack()
+            .unwrap(),
+        }],
         Some(&context.payer.pubkey()),
         &[&context.payer],
         context.last_blockhash,
@@ -696,3 +861,4575 @@ async fn reallocate_fail_unsigned() {
         TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
     );
 }
+
+#[tokio::test]
+async fn set_weighting_config_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(account_data.schema(), Some(RecordSchema::WeightingConfig));
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account.data),
+        Some(&WeightingConfigState::new(new_config))
+    );
+}
+
+#[tokio::test]
+async fn set_weighting_config_fail_invalid_mode() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let new_config = WeightingConfig {
+        weighting_mode: 7,
+        ..config
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::InvalidWeightingMode as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_weighting_config_fail_invalid_time_constant() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let new_config = WeightingConfig::new(WeightingMode::Dynamic, 0);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::InvalidTimeConstant as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_weighting_config_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+            &config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn write_typed_record_fail_invalid_config() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[7]).unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::InvalidWeightingMode as u32)
+        )
+    );
+
+    // a raw write that keeps the config valid is accepted
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            0,
+            &[WeightingMode::Static as u8],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = WeightingConfigState::unpack_from_record(&account.data).unwrap();
+    assert_eq!(state.active.weighting_mode(), Some(WeightingMode::Static));
+}
+
+#[tokio::test]
+async fn schedule_weighting_config_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let activation_epoch = clock.epoch + 1;
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+            activation_epoch,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = *WeightingConfigState::unpack_from_record(&account_handle.data).unwrap();
+    assert_eq!(state.active, config);
+    assert_eq!(state.pending, new_config);
+    assert_eq!(state.activation_epoch(), Some(activation_epoch));
+    assert_eq!(state.effective_config(&clock), &config);
+
+    context.warp_to_epoch(activation_epoch).unwrap();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(state.effective_config(&clock), &new_config);
+
+    // scheduling again promotes the change that already took effect
+    let later_config = WeightingConfig::new(WeightingMode::Dynamic, 2_000);
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &later_config,
+            clock.epoch + 1,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = WeightingConfigState::unpack_from_record(&account_handle.data).unwrap();
+    assert_eq!(state.active, new_config);
+    assert_eq!(state.pending, later_config);
+    assert_eq!(state.effective_config(&clock), &new_config);
+}
+
+#[tokio::test]
+async fn schedule_weighting_config_fail_past_epoch() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &config,
+            clock.epoch,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::InvalidActivationEpoch as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn schedule_weighting_config_fail_raw_record() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[0u8; 64];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &config,
+            u64::MAX,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn propose_accept_authority_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::propose_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.authority, authority.pubkey());
+    assert_eq!(
+        account_data.pending_authority(),
+        Some(&new_authority.pubkey())
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::accept_authority(
+            &account.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.authority, new_authority.pubkey());
+    assert_eq!(account_data.pending_authority(), None);
+
+    // the old authority has lost control
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[200u8; 8]).unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn propose_authority_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::propose_authority(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+            &wrong_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn accept_authority_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+    let wrong_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction::accept_authority(&account.pubkey(), &wrong_authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn accept_authority_fail_unsigned() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            Instruction {
+                program_id: id(),
+                accounts: vec![
+                    AccountMeta::new(account.pubkey(), false),
+                    AccountMeta::new_readonly(new_authority.pubkey(), false),
+                ],
+                data: instruction::RecordInstruction::AcceptAuthority
+                    .pack()
+                    .unwrap(),
+            },
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn cancel_authority_transfer_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction::cancel_authority_transfer(&account.pubkey(), &authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::accept_authority(
+            &account.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NoPendingAuthority as u32)
+        )
+    );
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.authority, authority.pubkey());
+}
+
+async fn set_signer_set(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+    threshold: u8,
+    signers: &[Pubkey],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_signers(&account.pubkey(), &authority.pubkey(), threshold, signers)
+                .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn multisig_write_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+    set_signer_set(&mut context, &authority, &account, 2, &signer_pubkeys).await;
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.signers(), signer_pubkeys.as_slice());
+    assert_eq!(account_data.threshold, 2);
+
+    let new_data = &[200u8; 8];
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_additional_signers(
+            instruction::write(&account.pubkey(), &signers[0].pubkey(), 0, new_data).unwrap(),
+            &[&signers[2].pubkey()],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[0], &signers[2]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        new_data
+    );
+}
+
+#[tokio::test]
+async fn multisig_write_fail_not_enough_signers() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+    set_signer_set(&mut context, &authority, &account, 2, &signer_pubkeys).await;
+
+    // the same signer listed twice only counts once
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_additional_signers(
+            instruction::write(&account.pubkey(), &signers[0].pubkey(), 0, &[200u8; 8]).unwrap(),
+            &[&signers[0].pubkey()],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[0]],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NotEnoughSigners as u32)
+        )
+    );
+
+    // the record authority no longer approves changes on its own
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[200u8; 8]).unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NotEnoughSigners as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn multisig_set_signers_back_to_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let signers = [Keypair::new(), Keypair::new()];
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+    set_signer_set(&mut context, &authority, &account, 2, &signer_pubkeys).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_additional_signers(
+            instruction::set_signers(&account.pubkey(), &signers[0].pubkey(), 0, &[]).unwrap(),
+            &[&signers[1].pubkey()],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[0], &signers[1]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let new_data = &[200u8; 8];
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(&account.pubkey(), &authority.pubkey(), 0, new_data).unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn set_signers_fail_invalid_threshold() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let signer = Pubkey::new_unique();
+    for (threshold, signers) in [
+        (2, vec![signer]),
+        (1, vec![signer, signer]),
+        (0, vec![signer]),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::set_signers(
+                &account.pubkey(),
+                &authority.pubkey(),
+                threshold,
+                &signers,
+            )
+            .unwrap()],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::InvalidSignerSet as u32)
+            )
+        );
+    }
+}
+
+async fn create_proposal(
+    context: &mut ProgramTestContext,
+    proposal: &Keypair,
+    record: &Pubkey,
+    proposer: &Keypair,
+    expiry_slot: u64,
+    data: &[u8],
+) {
+    let account_length = ProposalData::DATA_START_INDEX
+        .checked_add(data.len())
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &proposal.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+                account_length as u64,
+                &id(),
+            ),
+            instruction::create_proposal(
+                &proposal.pubkey(),
+                record,
+                &proposer.pubkey(),
+                expiry_slot,
+                0,
+                data,
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, proposal, proposer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn proposal_execute_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+    set_signer_set(&mut context, &authority, &account, 2, &signer_pubkeys).await;
+
+    let proposal = Keypair::new();
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    create_proposal(
+        &mut context,
+        &proposal,
+        &account.pubkey(),
+        &signers[0],
+        u64::MAX,
+        bytemuck::bytes_of(&new_config),
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::execute_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NotEnoughSigners as u32)
+        )
+    );
+
+    // a later approval in a separate transaction completes the threshold
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::approve_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+            &signers[2].pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[2]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::execute_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = WeightingConfigState::unpack_from_record(&account_handle.data).unwrap();
+    assert_eq!(state.active, new_config);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::approve_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+            &signers[1].pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[1]],
+        recent_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::ProposalAlreadyExecuted as u32)
+        )
+    );
+
+    // the proposer reclaims the rent
+    let recipient = Pubkey::new_unique();
+    let proposal_lamports = context
+        .banks_client
+        .get_account(proposal.pubkey())
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::cancel_proposal(
+            &proposal.pubkey(),
+            &signers[0].pubkey(),
+            &recipient,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &signers[0]],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let recipient = context
+        .banks_client
+        .get_account(recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(recipient.lamports, proposal_lamports);
+}
+
+#[tokio::test]
+async fn proposal_approve_fail_not_approver() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let proposal = Keypair::new();
+    create_proposal(
+        &mut context,
+        &proposal,
+        &account.pubkey(),
+        &authority,
+        u64::MAX,
+        &[200u8; 8],
+    )
+    .await;
+
+    let wrong_approver = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::approve_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+            &wrong_approver.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_approver],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn proposal_execute_fail_expired() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let expiry_slot = clock.slot + 10;
+    let proposal = Keypair::new();
+    create_proposal(
+        &mut context,
+        &proposal,
+        &account.pubkey(),
+        &authority,
+        expiry_slot,
+        &[200u8; 8],
+    )
+    .await;
+
+    context.warp_to_slot(expiry_slot).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::execute_proposal(
+            &proposal.pubkey(),
+            &account.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::ProposalExpired as u32)
+        )
+    );
+}
+
+async fn enable_history(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+    capacity: u8,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_history_capacity(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            capacity,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn history_records_changes() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 2).await;
+
+    for tc_ms in [1_000, 2_000] {
+        let new_config = WeightingConfig::new(WeightingMode::Dynamic, tc_ms);
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::set_weighting_config(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_config,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(u64::from(account_data.history_count), 3);
+
+    // the oldest change, enabling the history, was overwritten
+    let history = HistoryEntry::unpack_history(&account_handle.data)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(history.len(), 2);
+    assert_eq!(u64::from(history[0].state.active.tc_ms), 1_000);
+    assert_eq!(u64::from(history[1].state.active.tc_ms), 2_000);
+    assert!(history
+        .iter()
+        .all(|entry| entry.signer == authority.pubkey()));
+}
+
+#[tokio::test]
+async fn write_fail_into_history() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 2).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            std::mem::size_of::<WeightingConfigState>() as u64,
+            &[0; 8],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall)
+    );
+}
+
+#[tokio::test]
+async fn set_history_capacity_grows_account() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 2).await;
+
+    let account_data = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let history_end_index = RecordData::history_end_index_for(2);
+    assert_eq!(account_data.data.len(), history_end_index);
+    assert!(Rent::default().is_exempt(account_data.lamports, history_end_index));
+    let header = RecordData::unpack(&account_data.data).unwrap();
+    assert!(header.has_history());
+}
+
+#[tokio::test]
+async fn set_history_capacity_keeps_newest_entries() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 3).await;
+
+    let identity = Pubkey::new_unique();
+    let mut instructions = vec![instruction::add_to_list(
+        &account.pubkey(),
+        &authority.pubkey(),
+        &context.payer.pubkey(),
+        &identity,
+    )];
+    instructions.extend([1_000, 2_000].map(|tc_ms| {
+        instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &WeightingConfig::new(WeightingMode::Dynamic, tc_ms),
+        )
+    }));
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // growing keeps every change, shrinking the newest ones that fit
+    for (capacity, expected_tc_ms) in [(4, vec![30_000, 1_000, 2_000]), (2, vec![1_000, 2_000])] {
+        enable_history(&mut context, &authority, &account, capacity).await;
+        let account_handle = context
+            .banks_client
+            .get_account(account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let history = HistoryEntry::unpack_history(&account_handle.data)
+            .unwrap()
+            .map(|entry| u64::from(entry.state.active.tc_ms))
+            .collect::<Vec<_>>();
+        assert_eq!(history, expected_tc_ms);
+        assert!(is_listed(&account_handle.data, &identity));
+    }
+
+    // and the kept changes can be rolled back to
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::rollback(
+            &account.pubkey(),
+            &authority.pubkey(),
+            1,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        u64::from(
+            WeightingConfigState::unpack_from_record(&account_handle.data)
+                .unwrap()
+                .active
+                .tc_ms
+        ),
+        1_000
+    );
+}
+#[tokio::test]
+async fn rollback_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 4).await;
+
+    let bad_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &bad_config),
+            instruction::schedule_weighting_config(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &bad_config,
+                u64::MAX,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::rollback(
+            &account.pubkey(),
+            &authority.pubkey(),
+            2,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = WeightingConfigState::unpack_from_record(&account_handle.data).unwrap();
+    assert_eq!(*state, WeightingConfigState::new(config));
+    let history = HistoryEntry::unpack_history(&account_handle.data)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[3].state, *state);
+}
+
+#[tokio::test]
+async fn rollback_restores_activated_schedule() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 4).await;
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let activation_epoch = clock.epoch + 1;
+    let scheduled_config = WeightingConfig::new(WeightingMode::Dynamic, 10_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &scheduled_config,
+            activation_epoch,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the scheduled change takes effect before the next change replaces it
+    context.warp_to_epoch(activation_epoch).unwrap();
+    let bad_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &bad_config),
+            instruction::rollback(&account.pubkey(), &authority.pubkey(), 1),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let state = WeightingConfigState::unpack_from_record(&account_handle.data).unwrap();
+    assert_eq!(*state, WeightingConfigState::new(scheduled_config));
+}
+
+#[tokio::test]
+async fn rollback_fail_version_not_in_history() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 4).await;
+
+    for versions_back in [0, 1] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::rollback(
+                &account.pubkey(),
+                &authority.pubkey(),
+                versions_back,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::VersionNotInHistory as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn write_if_sequence_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    // the initial write is the first change
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let sequence = u64::from(RecordData::unpack(&account_handle.data).unwrap().sequence);
+    assert_eq!(sequence, 1);
+
+    let new_data = &[200u8; 8];
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write_if_sequence(
+            &account.pubkey(),
+            &authority.pubkey(),
+            sequence,
+            0,
+            new_data,
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(u64::from(account_data.sequence), sequence + 1);
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        new_data
+    );
+}
+
+#[tokio::test]
+async fn write_if_sequence_fail_stale() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    // another writer got in first
+    let new_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction::write_if_sequence(
+                &account.pubkey(),
+                &authority.pubkey(),
+                1,
+                0,
+                &[200u8; 8],
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::StaleSequence as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn create_config_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let name = "testnet";
+    let (config_address, _) = find_config_address(&authority.pubkey(), name);
+
+    // funding the address in advance does not block the creation
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &config_address, 1),
+            instruction::create_config(&authority.pubkey(), &context.payer.pubkey(), name).unwrap(),
+            instruction::set_weighting_config(
+                &config_address,
+                &authority.pubkey(),
+                &WeightingConfig::new(WeightingMode::Dynamic, 30_000),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(config_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, id());
+    assert_eq!(account.data.len(), RecordData::HISTORY_START_INDEX);
+    assert_eq!(
+        RecordData::unpack(&account.data).unwrap().authority,
+        authority.pubkey()
+    );
+    assert!(WeightingConfigState::unpack_from_record(&account.data).is_some());
+    assert!(RecordData::unpack_verified(&account.data).is_some());
+
+    // the same authority and name always map to the same account
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_config(&authority.pubkey(), &context.payer.pubkey(), name).unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    assert!(context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn create_config_fail_wrong_address() {
+    let context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let mut instruction =
+        instruction::create_config(&authority.pubkey(), &context.payer.pubkey(), "testnet")
+            .unwrap();
+    instruction.accounts[0].pubkey = find_config_address(&authority.pubkey(), "devnet").0;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn freeze_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::freeze(&account.pubkey(), &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(RecordData::unpack(&account_handle.data)
+        .unwrap()
+        .is_frozen());
+
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let new_authority = Keypair::new();
+    for instruction in [
+        instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[200u8; 8]).unwrap(),
+        instruction::set_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        ),
+        instruction::reallocate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            16,
+        ),
+        instruction::close_account(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &Pubkey::new_unique(),
+        ),
+        instruction::freeze(&account.pubkey(), &authority.pubkey()),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            recent_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::AccountFrozen as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn freeze_fail_incomplete() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    // a writer marks its change as in progress
+    let writer = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_writer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &writer.pubkey(),
+                true,
+            ),
+            instruction::set_complete(&account.pubkey(), &writer.pubkey(), false),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::freeze(&account.pubkey(), &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_complete(&account.pubkey(), &writer.pubkey(), true),
+            instruction::freeze(&account.pubkey(), &authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+#[tokio::test]
+async fn freeze_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::freeze(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_override_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let identity = Pubkey::new_unique();
+    let (override_address, _) = find_override_address(&account.pubkey(), &identity);
+    let overrides = WeightingConfigOverride {
+        fields: WeightingConfigOverride::TC_MS,
+        config: WeightingConfig::new(WeightingMode::Static, 5_000),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_override(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &identity,
+            &overrides,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record_account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let override_account = context
+        .banks_client
+        .get_account(override_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(override_account.owner, id());
+    let override_data = OverrideData::unpack(&override_account.data).unwrap();
+    assert_eq!(override_data.config, account.pubkey());
+    assert_eq!(override_data.identity, identity);
+    assert_eq!(override_data.overrides, overrides);
+
+    // only the time constant is replaced, other validators keep the config
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(
+        resolve_config(
+            &account.pubkey(),
+            &record_account.data,
+            &identity,
+            Some(&override_account.data),
+            &clock,
+        ),
+        Some(WeightingConfig::new(WeightingMode::Dynamic, 5_000))
+    );
+    assert_eq!(
+        resolve_config(
+            &account.pubkey(),
+            &record_account.data,
+            &Pubkey::new_unique(),
+            Some(&override_account.data),
+            &clock,
+        ),
+        Some(config)
+    );
+
+    // an existing override is replaced
+    let overrides = WeightingConfigOverride {
+        fields: WeightingConfigOverride::ALL_FIELDS,
+        config: WeightingConfig::new(WeightingMode::Static, 1_000),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_override(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &identity,
+            &overrides,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let override_account = context
+        .banks_client
+        .get_account(override_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        resolve_config(
+            &account.pubkey(),
+            &record_account.data,
+            &identity,
+            Some(&override_account.data),
+            &clock,
+        ),
+        Some(overrides.config)
+    );
+
+    let receiver = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::clear_override(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &receiver,
+            &identity,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(override_address)
+        .await
+        .unwrap()
+        .is_none());
+    let receiver_account = context
+        .banks_client
+        .get_account(receiver)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(receiver_account.lamports, override_account.lamports);
+}
+
+#[tokio::test]
+async fn set_override_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_override(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+            &context.payer.pubkey(),
+            &Pubkey::new_unique(),
+            &WeightingConfigOverride {
+                fields: WeightingConfigOverride::WEIGHTING_MODE,
+                config: WeightingConfig::new(WeightingMode::Static, 0),
+            },
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_override_fail_invalid_time_constant() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_override(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &Pubkey::new_unique(),
+            &WeightingConfigOverride {
+                fields: WeightingConfigOverride::TC_MS,
+                config: WeightingConfig::new(WeightingMode::Dynamic, 0),
+            },
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::InvalidTimeConstant as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn identity_list_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    enable_history(&mut context, &authority, &account, 1).await;
+
+    let identities = [
+        Pubkey::new_from_array([3; 32]),
+        Pubkey::new_from_array([1; 32]),
+        Pubkey::new_from_array([2; 32]),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &identities
+            .iter()
+            .map(|identity| {
+                instruction::add_to_list(
+                    &account.pubkey(),
+                    &authority.pubkey(),
+                    &context.payer.pubkey(),
+                    identity,
+                )
+            })
+            .collect::<Vec<_>>(),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_handle.data.len(), account_data.list_end_index());
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(account_handle.data.len())
+    );
+    assert_eq!(
+        RecordData::unpack_identity_list(&account_handle.data).unwrap(),
+        &[identities[1], identities[2], identities[0]]
+    );
+    assert!(is_listed(&account_handle.data, &identities[2]));
+    assert!(!is_listed(&account_handle.data, &Pubkey::new_unique()));
+
+    // the list moves along when the history grows
+    let list_size = 3 * std::mem::size_of::<Pubkey>();
+    let data_length =
+        RecordData::history_end_index_for(2) + list_size - RecordData::WRITABLE_START_INDEX;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::reallocate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &context.payer.pubkey(),
+                data_length as u64,
+            ),
+            instruction::set_history_capacity(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                2,
+            ),
+            instruction::remove_from_list(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &identities[2],
+            ),
+            instruction::set_list_mode(&account.pubkey(), &authority.pubkey(), ListMode::Allow),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.list_mode(), Some(ListMode::Allow));
+    assert_eq!(
+        account_handle.data.len(),
+        RecordData::history_end_index_for(2) + 2 * std::mem::size_of::<Pubkey>()
+    );
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(account_handle.data.len())
+    );
+    assert_eq!(
+        RecordData::unpack_identity_list(&account_handle.data).unwrap(),
+        &[identities[1], identities[0]]
+    );
+    assert!(!is_listed(&account_handle.data, &identities[2]));
+}
+
+#[tokio::test]
+async fn add_to_list_fail_already_listed() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let identity = Pubkey::new_unique();
+    let add_to_list = instruction::add_to_list(
+        &account.pubkey(),
+        &authority.pubkey(),
+        &context.payer.pubkey(),
+        &identity,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[add_to_list.clone(), add_to_list],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::AlreadyListed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn remove_from_list_fail_not_listed() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::remove_from_list(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &Pubkey::new_unique(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NotListed as u32)
+        )
+    );
+}
+
+async fn create_multiplier_table(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_multiplier_table(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            5_000,
+            20_000,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn multiplier_table_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    create_multiplier_table(&mut context, &authority, &account).await;
+
+    let (table_address, _) = find_multiplier_table_address(&account.pubkey());
+    let identities = [
+        Pubkey::new_from_array([3; 32]),
+        Pubkey::new_from_array([1; 32]),
+        Pubkey::new_from_array([2; 32]),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_multipliers(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &[
+                    MultiplierEntry::new(identities[0], 5_000),
+                    MultiplierEntry::new(identities[1], 15_000),
+                ],
+            )
+            .unwrap(),
+            // updates the existing entry and inserts a new one
+            instruction::set_multipliers(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &[
+                    MultiplierEntry::new(identities[1], 20_000),
+                    MultiplierEntry::new(identities[2], 10_000),
+                ],
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let table_account = context
+        .banks_client
+        .get_account(table_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(table_account.owner, id());
+    assert_eq!(
+        table_account.lamports,
+        Rent::default().minimum_balance(table_account.data.len())
+    );
+    let (table_data, entries) = MultiplierTableData::unpack(&table_account.data).unwrap();
+    assert_eq!(table_data.config, account.pubkey());
+    assert_eq!(
+        entries,
+        &[
+            MultiplierEntry::new(identities[1], 20_000),
+            MultiplierEntry::new(identities[2], 10_000),
+            MultiplierEntry::new(identities[0], 5_000),
+        ]
+    );
+    assert_eq!(
+        MultiplierTableData::multiplier_bps(&table_account.data, &identities[0]),
+        Some(5_000)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::remove_multipliers(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &[identities[1], identities[0]],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let table_account = context
+        .banks_client
+        .get_account(table_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        table_account.data.len(),
+        MultiplierTableData::entries_end_index_for(1)
+    );
+    assert_eq!(
+        table_account.lamports,
+        Rent::default().minimum_balance(table_account.data.len())
+    );
+    let (_, entries) = MultiplierTableData::unpack(&table_account.data).unwrap();
+    assert_eq!(entries, &[MultiplierEntry::new(identities[2], 10_000)]);
+}
+
+#[tokio::test]
+async fn set_multipliers_fail_out_of_range() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    create_multiplier_table(&mut context, &authority, &account).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_multipliers(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &[MultiplierEntry::new(Pubkey::new_unique(), 20_001)],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::MultiplierOutOfRange as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn remove_multipliers_fail_not_found() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    create_multiplier_table(&mut context, &authority, &account).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::remove_multipliers(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &[Pubkey::new_unique()],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::MultiplierNotFound as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_multipliers_fail_raw_record() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[111u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_multipliers(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &[MultiplierEntry::new(Pubkey::new_unique(), 10_000)],
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn set_multiplier_range_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    create_multiplier_table(&mut context, &authority, &account).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_multipliers(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &[MultiplierEntry::new(Pubkey::new_unique(), 10_000)],
+            )
+            .unwrap(),
+            instruction::set_multiplier_range(
+                &account.pubkey(),
+                &authority.pubkey(),
+                8_000,
+                40_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (table_address, _) = find_multiplier_table_address(&account.pubkey());
+    let table_account = context
+        .banks_client
+        .get_account(table_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let (table_data, entries) = MultiplierTableData::unpack(&table_account.data).unwrap();
+    assert!(table_data.accepts(40_000));
+    assert!(!table_data.accepts(7_999));
+    assert_eq!(entries.len(), 1);
+
+    // the new range must hold every entry and must not be empty
+    for (min_multiplier_bps, max_multiplier_bps, error) in [
+        (
+            11_000,
+            40_000,
+            InstructionError::Custom(RecordError::MultiplierOutOfRange as u32),
+        ),
+        (20_000, 10_000, InstructionError::InvalidArgument),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::set_multiplier_range(
+                &account.pubkey(),
+                &authority.pubkey(),
+                min_multiplier_bps,
+                max_multiplier_bps,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+}
+
+#[tokio::test]
+async fn close_multiplier_table_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+    create_multiplier_table(&mut context, &authority, &account).await;
+
+    let (table_address, _) = find_multiplier_table_address(&account.pubkey());
+    let table_lamports = context
+        .banks_client
+        .get_account(table_address)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let receiver = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_multiplier_table(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &receiver,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert!(context
+        .banks_client
+        .get_account(table_address)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(receiver)
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports,
+        table_lamports
+    );
+
+    // a closed table can be created again
+    create_multiplier_table(&mut context, &authority, &account).await;
+}
+#[tokio::test]
+async fn content_hash_tracks_changes() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 16];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack_verified(&account_handle.data).unwrap();
+    assert_eq!(
+        account_data.content_hash,
+        RecordData::compute_content_hash(data)
+    );
+
+    // the first half of a change spread over two transactions
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_complete(&account.pubkey(), &authority.pubkey(), false),
+            instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[222u8; 8]).unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert!(!account_data.is_complete());
+    assert_eq!(
+        account_data.content_hash,
+        RecordData::compute_content_hash(&account_handle.data[RecordData::WRITABLE_START_INDEX..])
+    );
+    assert!(RecordData::unpack_verified(&account_handle.data).is_none());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::write(&account.pubkey(), &authority.pubkey(), 8, &[222u8; 8]).unwrap(),
+            instruction::set_complete(&account.pubkey(), &authority.pubkey(), true),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack_verified(&account_handle.data).unwrap();
+    assert_eq!(
+        account_data.content_hash,
+        RecordData::compute_content_hash(&[222u8; 16])
+    );
+}
+
+async fn begin_staged_write(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::begin_staged_write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn staged_write_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 16];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    begin_staged_write(&mut context, &authority, &account).await;
+
+    let new_data = [222u8; 16];
+    for (offset, chunk) in [(0, &new_data[..8]), (8, &new_data[8..])] {
+        // readers keep seeing the complete old data while chunks land
+        let account_handle = context
+            .banks_client
+            .get_account(account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(RecordData::unpack_verified(&account_handle.data).is_some());
+        assert_eq!(
+            &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+            data
+        );
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                instruction::stage_chunk(&account.pubkey(), &authority.pubkey(), offset, chunk)
+                    .unwrap(),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    let (staging_address, _) = find_staging_address(&account.pubkey());
+    let staging_account = context
+        .banks_client
+        .get_account(staging_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let (staging_data, staged_data) = StagingData::unpack(&staging_account.data).unwrap();
+    assert_eq!(staging_data.record, account.pubkey());
+    assert_eq!(staged_data, new_data);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::commit_staged_write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            RecordData::compute_content_hash(&new_data),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(RecordData::unpack_verified(&account_handle.data).is_some());
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        new_data
+    );
+    assert!(context
+        .banks_client
+        .get_account(staging_address)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn staged_write_abort() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 16];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    begin_staged_write(&mut context, &authority, &account).await;
+
+    let new_data = [222u8; 16];
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::stage_chunk(&account.pubkey(), &authority.pubkey(), 0, &new_data).unwrap(),
+            instruction::abort_staged_write(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (staging_address, _) = find_staging_address(&account.pubkey());
+    assert!(context
+        .banks_client
+        .get_account(staging_address)
+        .await
+        .unwrap()
+        .is_none());
+
+    // nothing is left to commit
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::commit_staged_write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            RecordData::compute_content_hash(&new_data),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        data
+    );
+}
+
+#[tokio::test]
+async fn commit_staged_write_fail_hash_mismatch() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 16];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    begin_staged_write(&mut context, &authority, &account).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::stage_chunk(&account.pubkey(), &authority.pubkey(), 0, &[222u8; 8])
+                .unwrap(),
+            instruction::commit_staged_write(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                RecordData::compute_content_hash(&[222u8; 16]),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::StagedHashMismatch as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn commit_staged_write_fail_record_changed() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 16];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    begin_staged_write(&mut context, &authority, &account).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::write(&account.pubkey(), &authority.pubkey(), 0, &[200u8; 8]).unwrap(),
+            instruction::commit_staged_write(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                RecordData::compute_content_hash(data),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::StaleSequence as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn writer_role_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let writer = Keypair::new();
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_writer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &writer.pubkey(),
+                true,
+            ),
+            instruction::set_weighting_config(&account.pubkey(), &writer.pubkey(), &new_config),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        RecordData::unpack(&account_handle.data).unwrap().writers(),
+        &[writer.pubkey()]
+    );
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data)
+            .unwrap()
+            .active,
+        new_config
+    );
+
+    // a removed writer can no longer write
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_writer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &writer.pubkey(),
+                false,
+            ),
+            instruction::set_weighting_config(&account.pubkey(), &writer.pubkey(), &config),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &writer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn writer_role_fail_admin_instructions() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let writer = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_writer(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &writer.pubkey(),
+            true,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    for instruction in [
+        instruction::set_authority(&account.pubkey(), &writer.pubkey(), &writer.pubkey()),
+        instruction::set_writer(
+            &account.pubkey(),
+            &writer.pubkey(),
+            &Pubkey::new_unique(),
+            true,
+        ),
+        instruction::schedule_weighting_config(&account.pubkey(), &writer.pubkey(), &config, 10),
+        instruction::close_account(&account.pubkey(), &writer.pubkey(), &writer.pubkey()),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &writer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn writer_role_fail_raw_schedule() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let writer = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_writer(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &writer.pubkey(),
+            true,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // a writer cannot schedule a change by writing the config state, even
+    // for the current epoch, which `ScheduleWeightingConfig` refuses
+    let scheduled_state = WeightingConfigState {
+        active: config,
+        pending: WeightingConfig::new(WeightingMode::Static, 1_000),
+        activation_epoch: 1.into(),
+    };
+    let sequence = u64::from(
+        RecordData::unpack(
+            &context
+                .banks_client
+                .get_account(account.pubkey())
+                .await
+                .unwrap()
+                .unwrap()
+                .data,
+        )
+        .unwrap()
+        .sequence,
+    );
+    for instruction in [
+        instruction::write(
+            &account.pubkey(),
+            &writer.pubkey(),
+            0,
+            bytemuck::bytes_of(&scheduled_state),
+        )
+        .unwrap(),
+        instruction::write_if_sequence(
+            &account.pubkey(),
+            &writer.pubkey(),
+            sequence,
+            0,
+            bytemuck::bytes_of(&scheduled_state),
+        )
+        .unwrap(),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &writer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+            )
+        );
+    }
+
+    // writing the active config alone is still allowed
+    let new_config = WeightingConfig::new(WeightingMode::Dynamic, 10_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::write(
+            &account.pubkey(),
+            &writer.pubkey(),
+            0,
+            bytemuck::bytes_of(&new_config),
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data),
+        Some(&WeightingConfigState::new(new_config))
+    );
+}
+#[tokio::test]
+async fn emergency_static_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let emergency_authority = Keypair::new();
+    let scheduled = WeightingConfig::new(WeightingMode::Dynamic, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_emergency_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &emergency_authority.pubkey(),
+            ),
+            instruction::schedule_weighting_config(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &scheduled,
+                10,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(100).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::emergency_static(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &emergency_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(account_data.authority, authority.pubkey());
+    assert_eq!(
+        account_data.emergency_trigger(),
+        Some((&emergency_authority.pubkey(), 100))
+    );
+    // the time constant is kept and the scheduled config dropped
+    assert_eq!(
+        *WeightingConfigState::unpack_from_record(&account_handle.data).unwrap(),
+        WeightingConfigState::new(WeightingConfig::new(WeightingMode::Static, 30_000))
+    );
+}
+
+#[tokio::test]
+async fn emergency_static_keeps_activated_schedule() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let emergency_authority = Keypair::new();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let activation_epoch = clock.epoch + 1;
+    let scheduled = WeightingConfig::new(WeightingMode::Dynamic, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_emergency_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &emergency_authority.pubkey(),
+            ),
+            instruction::schedule_weighting_config(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &scheduled,
+                activation_epoch,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    context.warp_to_epoch(activation_epoch).unwrap();
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::emergency_static(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &emergency_authority],
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the time constant in effect is the scheduled one
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        *WeightingConfigState::unpack_from_record(&account_handle.data).unwrap(),
+        WeightingConfigState::new(WeightingConfig::new(WeightingMode::Static, 1_000))
+    );
+}
+
+#[tokio::test]
+async fn emergency_static_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    // without an emergency authority, not even the authority may use it
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::emergency_static(
+            &account.pubkey(),
+            &authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+
+    let emergency_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_emergency_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &emergency_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the emergency authority can make no other change
+    for instruction in [
+        instruction::set_weighting_config(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+            &config,
+        ),
+        instruction::set_authority(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+            &emergency_authority.pubkey(),
+        ),
+        instruction::set_emergency_authority(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+            &Pubkey::new_unique(),
+        ),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &emergency_authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+            )
+        );
+    }
+
+    // the emergency authority must sign
+    let mut instruction =
+        instruction::emergency_static(&account.pubkey(), &emergency_authority.pubkey());
+    instruction.accounts[1].is_signer = false;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn min_slots_between_writes() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let emergency_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_min_slots_between_writes(&account.pubkey(), &authority.pubkey(), 100),
+            instruction::set_emergency_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &emergency_authority.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(200).unwrap();
+    let new_config = WeightingConfig::new(WeightingMode::Dynamic, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = RecordData::unpack(&account_handle.data).unwrap();
+    assert_eq!(u64::from(account_data.last_write_slot), 200);
+    assert_eq!(account_data.next_write_slot(), 300);
+
+    // every config write path is held back
+    for instruction in [
+        instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &config),
+        instruction::write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            0,
+            bytemuck::bytes_of(&WeightingConfigState::new(config)),
+        )
+        .unwrap(),
+        instruction::schedule_weighting_config(&account.pubkey(), &authority.pubkey(), &config, 10),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::WriteTooSoon as u32)
+            )
+        );
+    }
+
+    // but not the emergency path
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::emergency_static(
+            &account.pubkey(),
+            &emergency_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &emergency_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    context.warp_to_slot(300).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data)
+            .unwrap()
+            .active,
+        config
+    );
+}
+
+async fn create_bounds(
+    context: &mut ProgramTestContext,
+    governance_authority: &Keypair,
+    bounds: &Keypair,
+    min_tc_ms: u64,
+    max_tc_ms: u64,
+    allowed_modes: u8,
+) {
+    let account_length = std::mem::size_of::<BoundsData>();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &bounds.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+                account_length as u64,
+                &id(),
+            ),
+            instruction::create_bounds(
+                &bounds.pubkey(),
+                &governance_authority.pubkey(),
+                min_tc_ms,
+                max_tc_ms,
+                allowed_modes,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, bounds, governance_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn bounds_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let governance_authority = Keypair::new();
+    let bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &governance_authority,
+        &bounds,
+        10_000,
+        60_000,
+        1 << WeightingMode::Dynamic as u8,
+    )
+    .await;
+
+    let writer = Keypair::new();
+    let new_config = WeightingConfig::new(WeightingMode::Dynamic, 10_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_bounds(&account.pubkey(), &authority.pubkey(), &bounds.pubkey()),
+            instruction::set_writer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &writer.pubkey(),
+                true,
+            ),
+            instruction::with_bounds(
+                instruction::set_weighting_config(&account.pubkey(), &writer.pubkey(), &new_config),
+                &bounds.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        RecordData::unpack(&account_handle.data).unwrap().bounds(),
+        Some(&bounds.pubkey())
+    );
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data)
+            .unwrap()
+            .active,
+        new_config
+    );
+
+    // widened by governance, the writer may push further
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::update_bounds(
+                &bounds.pubkey(),
+                &governance_authority.pubkey(),
+                1_000,
+                60_000,
+                BoundsData::ALL_MODES,
+            ),
+            instruction::with_bounds(
+                instruction::set_weighting_config(&account.pubkey(), &writer.pubkey(), &new_config),
+                &bounds.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &governance_authority, &writer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data)
+            .unwrap()
+            .active,
+        new_config
+    );
+}
+
+#[tokio::test]
+async fn bounds_fail_out_of_bounds() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let governance_authority = Keypair::new();
+    let bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &governance_authority,
+        &bounds,
+        10_000,
+        60_000,
+        1 << WeightingMode::Dynamic as u8,
+    )
+    .await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_bounds(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &bounds.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let too_short = WeightingConfig::new(WeightingMode::Dynamic, 9_999);
+    let static_config = WeightingConfig::new(WeightingMode::Static, 30_000);
+    for instruction in [
+        instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &too_short),
+        instruction::set_weighting_config(&account.pubkey(), &authority.pubkey(), &static_config),
+        instruction::write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            0,
+            bytemuck::bytes_of(&WeightingConfigState::new(too_short)),
+        )
+        .unwrap(),
+        instruction::schedule_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &too_short,
+            10,
+        ),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::with_bounds(instruction, &bounds.pubkey())],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::ConfigOutOfBounds as u32)
+            )
+        );
+    }
+
+    // the bounds account must be provided
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // only governance may change the bounds
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_bounds(
+            &bounds.pubkey(),
+            &authority.pubkey(),
+            1,
+            WeightingConfig::MAX_TC_MS,
+            BoundsData::ALL_MODES,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn create_bounds_fail_missing_governance_signature() {
+    let context = program_test().start_with_context().await;
+
+    let governance_authority = Keypair::new();
+    let bounds = Keypair::new();
+    let account_length = std::mem::size_of::<BoundsData>();
+    let mut create_bounds = instruction::create_bounds(
+        &bounds.pubkey(),
+        &governance_authority.pubkey(),
+        10_000,
+        60_000,
+        BoundsData::ALL_MODES,
+    );
+    create_bounds.accounts[1].is_signer = false;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &bounds.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+                account_length as u64,
+                &id(),
+            ),
+            create_bounds,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &bounds],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn set_bounds_needs_governance_to_remove_or_replace() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let governance_authority = Keypair::new();
+    let bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &governance_authority,
+        &bounds,
+        10_000,
+        60_000,
+        BoundsData::ALL_MODES,
+    )
+    .await;
+    let own_bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &authority,
+        &own_bounds,
+        1,
+        WeightingConfig::MAX_TC_MS,
+        BoundsData::ALL_MODES,
+    )
+    .await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_bounds(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &bounds.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the record authority alone can neither remove nor swap the bounds
+    for new_bounds in [Pubkey::default(), own_bounds.pubkey()] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::with_bounds(
+                instruction::set_bounds(&account.pubkey(), &authority.pubkey(), &new_bounds),
+                &bounds.pubkey(),
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_bounds_authority(
+            instruction::set_bounds(&account.pubkey(), &authority.pubkey(), &Pubkey::default()),
+            &bounds.pubkey(),
+            &governance_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &governance_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        RecordData::unpack(&account_handle.data).unwrap().bounds(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn set_bounds_fail_invalid_bounds() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let governance_authority = Keypair::new();
+    let narrow_bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &governance_authority,
+        &narrow_bounds,
+        10_000,
+        20_000,
+        BoundsData::ALL_MODES,
+    )
+    .await;
+
+    let other_account = Keypair::new();
+    initialize_weighting_config_account(&mut context, &authority, &other_account, &config).await;
+
+    for (bounds, error) in [
+        (Pubkey::new_unique(), InstructionError::IncorrectProgramId),
+        (other_account.pubkey(), InstructionError::InvalidAccountData),
+        (
+            narrow_bounds.pubkey(),
+            InstructionError::Custom(RecordError::ConfigOutOfBounds as u32),
+        ),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::set_bounds(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &bounds,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+
+    // the new bounds account is needed to check it
+    let mut set_bounds = instruction::set_bounds(
+        &account.pubkey(),
+        &authority.pubkey(),
+        &narrow_bounds.pubkey(),
+    );
+    set_bounds.accounts.pop();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_bounds],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
+#[tokio::test]
+async fn set_override_fail_out_of_bounds() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let governance_authority = Keypair::new();
+    let bounds = Keypair::new();
+    create_bounds(
+        &mut context,
+        &governance_authority,
+        &bounds,
+        10_000,
+        60_000,
+        1 << WeightingMode::Dynamic as u8,
+    )
+    .await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_bounds(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &bounds.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let identity = Pubkey::new_unique();
+    let (override_address, _) = find_override_address(&account.pubkey(), &identity);
+    let static_mode = WeightingConfigOverride {
+        fields: WeightingConfigOverride::WEIGHTING_MODE,
+        config: WeightingConfig::new(WeightingMode::Static, 0),
+    };
+    let too_short = WeightingConfigOverride {
+        fields: WeightingConfigOverride::TC_MS,
+        config: WeightingConfig::new(WeightingMode::Dynamic, 9_999),
+    };
+    for overrides in [static_mode, too_short] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::with_bounds(
+                instruction::set_override(
+                    &account.pubkey(),
+                    &authority.pubkey(),
+                    &context.payer.pubkey(),
+                    &identity,
+                    &overrides,
+                ),
+                &bounds.pubkey(),
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::ConfigOutOfBounds as u32)
+            )
+        );
+    }
+    assert!(context
+        .banks_client
+        .get_account(override_address)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Start a test validator holding a record created before the header grew,
+/// at version 1
+async fn start_with_v1_record(
+    authority: &Pubkey,
+    record: &Pubkey,
+    data: &[u8],
+) -> ProgramTestContext {
+    let mut account_data = bytemuck::bytes_of(&RecordDataV1 {
+        version: RecordDataV1::VERSION,
+        authority: *authority,
+    })
+    .to_vec();
+    account_data.extend_from_slice(data);
+
+    let mut program_test = program_test();
+    program_test.add_account(
+        *record,
+        Account {
+            lamports: 1.max(Rent::default().minimum_balance(account_data.len())),
+            data: account_data,
+            owner: id(),
+            ..Account::default()
+        },
+    );
+    program_test.start_with_context().await
+}
+
+#[tokio::test]
+async fn migrate_v1_record_success() {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let context = start_with_v1_record(&authority.pubkey(), &account.pubkey(), &[111u8; 8]).await;
+
+    // the old record keeps accepting writes until it is migrated
+    let data = &[222u8; 4];
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::write(&account.pubkey(), &authority.pubkey(), 4, data).unwrap(),
+            instruction::migrate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account_handle.data.len(),
+        RecordData::WRITABLE_START_INDEX + 8
+    );
+    assert!(account_handle.lamports >= Rent::default().minimum_balance(account_handle.data.len()));
+    let account_data = RecordData::unpack_verified(&account_handle.data).unwrap();
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(account_data.authority, authority.pubkey());
+    assert_eq!(account_data.schema(), Some(RecordSchema::Raw));
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        &[111, 111, 111, 111, 222, 222, 222, 222]
+    );
+
+    // and takes every instruction afterwards
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::freeze(&account.pubkey(), &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn migrate_v1_record_fail() {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let mut context =
+        start_with_v1_record(&authority.pubkey(), &account.pubkey(), &[111u8; 8]).await;
+
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::migrate(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+
+    // instructions beyond the original ones need the current header
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::freeze(&account.pubkey(), &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // current records cannot be migrated
+    let new_account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &new_account, &[1u8; 8]).await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::migrate(
+            &new_account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn migrate_v1_legacy_config() {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    // config as version 1 clients laid it out: mode, then the time constant,
+    // padded to 32 bytes
+    let mut legacy_data = vec![WeightingMode::Dynamic as u8];
+    legacy_data.extend_from_slice(&30_000u64.to_le_bytes());
+    legacy_data.resize(RecordDataV1::LEGACY_CONFIG_LEN, 0);
+    let context = start_with_v1_record(&authority.pubkey(), &account.pubkey(), &legacy_data).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::migrate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account_handle.data.len(), RecordData::HISTORY_START_INDEX);
+    assert!(account_handle.lamports >= Rent::default().minimum_balance(account_handle.data.len()));
+    let account_data = RecordData::unpack_verified(&account_handle.data).unwrap();
+    assert_eq!(account_data.authority, authority.pubkey());
+    assert_eq!(account_data.schema(), Some(RecordSchema::WeightingConfig));
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    assert_eq!(
+        WeightingConfigState::unpack_from_record(&account_handle.data),
+        Some(&WeightingConfigState::new(config))
+    );
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(
+        resolve_config(
+            &account.pubkey(),
+            &account_handle.data,
+            &Pubkey::new_unique(),
+            None,
+            &clock
+        ),
+        Some(config)
+    );
+
+    // and takes typed config updates afterwards
+    let new_config = WeightingConfig::new(WeightingMode::Static, 1_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_weighting_config(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_config,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn v1_record_set_authority_and_close() {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let context = start_with_v1_record(&authority.pubkey(), &account.pubkey(), &[111u8; 8]).await;
+
+    let new_authority = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::set_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction::close_account(&account.pubkey(), &new_authority.pubkey(), &recipient),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &new_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert!(context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    assert!(context
+        .banks_client
+        .get_account(recipient)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+/// Program returning the effective config read from the record program
+/// through CPI as its own return data
+fn process_cpi_caller(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let [program_info, record_info, override_info] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let identity = Pubkey::try_from(input).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let config = cpi::get_effective_config(program_info, record_info, override_info, &identity)?;
+    set_return_data(bytemuck::bytes_of(&config));
+    Ok(())
+}
+
+/// Simulate a transaction holding a single instruction, returning its result
+/// and return data
+async fn simulate_return_data(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> (Result<(), TransactionError>, Option<(Pubkey, Vec<u8>)>) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    let return_data = simulation
+        .simulation_details
+        .unwrap()
+        .return_data
+        .map(|return_data| (return_data.program_id, return_data.data));
+    (simulation.result.unwrap(), return_data)
+}
+
+#[tokio::test]
+async fn get_effective_config_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let identity = Pubkey::new_unique();
+    let (result, return_data) = simulate_return_data(
+        &mut context,
+        instruction::get_effective_config(&account.pubkey(), &identity),
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(
+        return_data,
+        Some((id(), bytemuck::bytes_of(&config).to_vec()))
+    );
+
+    let overrides = WeightingConfigOverride {
+        fields: WeightingConfigOverride::TC_MS,
+        config: WeightingConfig::new(WeightingMode::Static, 5_000),
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_override(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            &identity,
+            &overrides,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (result, return_data) = simulate_return_data(
+        &mut context,
+        instruction::get_effective_config(&account.pubkey(), &identity),
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(
+        return_data,
+        Some((
+            id(),
+            bytemuck::bytes_of(&WeightingConfig::new(WeightingMode::Dynamic, 5_000)).to_vec()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn get_effective_config_fail() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[1u8; 8]).await;
+
+    // raw records hold no config
+    let identity = Pubkey::new_unique();
+    let (result, _) = simulate_return_data(
+        &mut context,
+        instruction::get_effective_config(&account.pubkey(), &identity),
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // the override account must belong to the identity
+    let mut instruction = instruction::get_effective_config(&account.pubkey(), &identity);
+    instruction.accounts[1].pubkey =
+        find_override_address(&account.pubkey(), &Pubkey::new_unique()).0;
+    let (result, _) = simulate_return_data(&mut context, instruction).await;
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn get_effective_config_through_cpi() {
+    let caller_id = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_program("cpi_caller", caller_id, processor!(process_cpi_caller));
+    let mut context = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let config = WeightingConfig::new(WeightingMode::Dynamic, 30_000);
+    initialize_weighting_config_account(&mut context, &authority, &account, &config).await;
+
+    let identity = Pubkey::new_unique();
+    let (override_address, _) = find_override_address(&account.pubkey(), &identity);
+    let (result, return_data) = simulate_return_data(
+        &mut context,
+        Instruction {
+            program_id: caller_id,
+            accounts: vec![
+                AccountMeta::new_readonly(id(), false),
+                AccountMeta::new_readonly(account.pubkey(), false),
+                AccountMeta::new_readonly(override_address, false),
+            ],
+            data: identity.to_bytes().to_vec(),
+        },
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(
+        return_data,
+        Some((caller_id, bytemuck::bytes_of(&config).to_vec()))
+    );
+}